@@ -0,0 +1,30 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Find a `[target.<target>]` setting like `linker` in the Cargo configuration
+///
+/// Configuration files are searched from `dir` upwards, so the nearest one wins like in
+/// Cargo. Returns the value and the configuration file it was read from. Relative paths
+/// containing a separator are relative to the directory holding `.cargo`.
+pub(crate) fn find_target_setting(
+    dir: &Path,
+    target: &str,
+    key: &str,
+) -> Option<(PathBuf, PathBuf)> {
+    dir.ancestors().find_map(|dir| {
+        // Cargo prefers the legacy `config` name when both files exist
+        let path = ["config", "config.toml"]
+            .iter()
+            .map(|name| dir.join(".cargo").join(name))
+            .find(|path| path.is_file())?;
+        let content = fs::read_to_string(&path).ok()?;
+        let config: toml::Table = content.parse().ok()?;
+        let value = config.get("target")?.get(target)?.get(key)?.as_str()?;
+        let value = if value.contains(['/', '\\']) {
+            dir.join(value)
+        } else {
+            PathBuf::from(value)
+        };
+        Some((value, path))
+    })
+}
@@ -0,0 +1,117 @@
+use std::env;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// Errors that can occur while resolving a CMake toolchain
+#[derive(Debug)]
+pub enum Error {
+    /// Failed to query the rustc version
+    RustcVersion {
+        /// Build target
+        target: String,
+        source: rustc_version::Error,
+    },
+    /// No C/C++ compiler could be found for the target
+    CompilerNotFound {
+        /// Build target
+        target: String,
+        source: cc::Error,
+    },
+    /// A configured tool doesn't exist or isn't executable
+    ToolNotFound {
+        /// Build target
+        target: String,
+        /// Tool name, like `cc` or `ar`
+        tool: String,
+        /// Configured path of the tool
+        path: PathBuf,
+    },
+    /// A language standard isn't known to CMake
+    InvalidStandard {
+        /// Build target
+        target: String,
+        /// Language, `C` or `C++`
+        language: String,
+        /// Configured standard, like `14`
+        standard: u32,
+    },
+    /// The `TARGET` environment variable set by Cargo is missing
+    TargetNotSet { source: env::VarError },
+    /// The `OUT_DIR` environment variable set by Cargo is missing
+    OutDirNotSet { source: env::VarError },
+    /// Failed to write a toolchain file
+    Io {
+        /// Build target
+        target: String,
+        /// File being written
+        path: PathBuf,
+        source: io::Error,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::RustcVersion { target, source } => write!(
+                f,
+                "failed to query rustc version for target {}: {}",
+                target, source
+            ),
+            Error::CompilerNotFound { target, source } => {
+                write!(f, "no compiler found for target {}: {}", target, source)
+            }
+            Error::ToolNotFound { target, tool, path } => write!(
+                f,
+                "{} not found for target {}: {}",
+                tool,
+                target,
+                path.display()
+            ),
+            Error::InvalidStandard {
+                target,
+                language,
+                standard,
+            } => write!(
+                f,
+                "unknown {} standard {} for target {}",
+                language, standard, target
+            ),
+            Error::TargetNotSet { source } => write!(
+                f,
+                "failed to read TARGET environment variable, not running in a build script?: {}",
+                source
+            ),
+            Error::OutDirNotSet { source } => write!(
+                f,
+                "failed to read OUT_DIR environment variable, not running in a build script?: {}",
+                source
+            ),
+            Error::Io {
+                target,
+                path,
+                source,
+            } => write!(
+                f,
+                "failed to write {} for target {}: {}",
+                path.display(),
+                target,
+                source
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::RustcVersion { source, .. } => Some(source),
+            Error::CompilerNotFound { source, .. } => Some(source),
+            Error::ToolNotFound { .. } => None,
+            Error::InvalidStandard { .. } => None,
+            Error::TargetNotSet { source } => Some(source),
+            Error::OutDirNotSet { source } => Some(source),
+            Error::Io { source, .. } => Some(source),
+        }
+    }
+}
@@ -1,332 +1,6343 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet};
 use std::env;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+pub use error::Error;
+pub use report::{ToolReport, ToolSource, ToolchainReport};
+pub use resolved::Resolved;
+use writer::CMakeWriter;
+
+#[cfg(feature = "cargo-config")]
+mod cargo_config;
+mod error;
+#[cfg(feature = "meson")]
+mod meson;
+mod report;
+mod resolved;
+mod writer;
+
+/// Search mode for the `CMAKE_FIND_ROOT_PATH_MODE_*` variables
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FindRootPathMode {
+    /// Only search the host system
+    Never,
+    /// Only search the `CMAKE_FIND_ROOT_PATH` locations
+    Only,
+    /// Search the `CMAKE_FIND_ROOT_PATH` locations first, then the host system
+    Both,
+}
+
+impl FindRootPathMode {
+    fn as_str(&self) -> &'static str {
+        match self {
+            FindRootPathMode::Never => "NEVER",
+            FindRootPathMode::Only => "ONLY",
+            FindRootPathMode::Both => "BOTH",
+        }
+    }
+}
+
+/// Kind of binary `try_compile` builds, `CMAKE_TRY_COMPILE_TARGET_TYPE`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TryCompileTargetType {
+    /// Link an executable, the CMake default
+    Executable,
+    /// Create a static library, which doesn't need a working linker setup
+    StaticLibrary,
+}
+
+impl TryCompileTargetType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            TryCompileTargetType::Executable => "EXECUTABLE",
+            TryCompileTargetType::StaticLibrary => "STATIC_LIBRARY",
+        }
+    }
+}
+
+/// Byte order of the target
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Endianness {
+    /// Little endian, like x86 and most ARM targets
+    Little,
+    /// Big endian, like s390x and sparc
+    Big,
+}
+
+impl Endianness {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Endianness::Little => "LITTLE_ENDIAN",
+            Endianness::Big => "BIG_ENDIAN",
+        }
+    }
+}
+
+/// C standards known to CMake
+const C_STANDARDS: [u32; 5] = [90, 99, 11, 17, 23];
+
+/// C++ standards known to CMake
+const CXX_STANDARDS: [u32; 6] = [98, 11, 14, 17, 20, 23];
+
+/// Family of the C/C++ compiler
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CompilerFamily {
+    /// GCC or a GCC compatible compiler
+    Gnu,
+    /// Clang
+    Clang,
+    /// MSVC `cl.exe` or `clang-cl`
+    Msvc,
+}
+
+impl CompilerFamily {
+    /// Guess the family from the compiler name, for compilers that weren't probed
+    fn from_path(path: &Path) -> Self {
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if name == "cl" || name.starts_with("clang-cl") {
+            CompilerFamily::Msvc
+        } else if name.contains("clang") {
+            CompilerFamily::Clang
+        } else {
+            CompilerFamily::Gnu
+        }
+    }
+
+    /// The `CMAKE_<LANG>_COMPILER_ID` CMake identifies the family with
+    fn cmake_compiler_id(self) -> &'static str {
+        match self {
+            CompilerFamily::Gnu => "GNU",
+            CompilerFamily::Clang => "Clang",
+            CompilerFamily::Msvc => "MSVC",
+        }
+    }
+
+    fn from_tool(tool: &cc::Tool) -> Self {
+        if tool.is_like_msvc() {
+            CompilerFamily::Msvc
+        } else if tool.is_like_clang() {
+            CompilerFamily::Clang
+        } else {
+            CompilerFamily::Gnu
+        }
+    }
+}
+
+/// Tool and flag environment variables, consulted with the target and `HOST_`/`TARGET_`
+/// variants
+const TOOL_ENV_VARS: [&str; 17] = [
+    "CC",
+    "CXX",
+    "FC",
+    "AR",
+    "RANLIB",
+    "STRIP",
+    "NM",
+    "OBJCOPY",
+    "OBJDUMP",
+    "RC",
+    "LD",
+    "MT",
+    "CFLAGS",
+    "CXXFLAGS",
+    "LDFLAGS",
+    "CMAKE_COMPILER_LAUNCHER",
+    "SDKROOT",
+];
+
+/// Names of the environment variables consulted while resolving the toolchain
+#[derive(Debug, Default)]
+struct ConsultedEnv(Mutex<BTreeSet<String>>);
+
+impl ConsultedEnv {
+    fn record(&self, var: &str) {
+        let mut vars = self.0.lock().unwrap_or_else(|err| err.into_inner());
+        if !vars.contains(var) {
+            vars.insert(var.to_string());
+        }
+    }
+
+    fn names(&self) -> Vec<String> {
+        let vars = self.0.lock().unwrap_or_else(|err| err.into_inner());
+        vars.iter().cloned().collect()
+    }
+}
+
+impl Clone for ConsultedEnv {
+    fn clone(&self) -> Self {
+        let vars = self.0.lock().unwrap_or_else(|err| err.into_inner());
+        Self(Mutex::new(vars.clone()))
+    }
+}
 
 /// CMake toolchain
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CMakeToolchain {
     /// Host target
     host: String,
     /// Build target
     target: String,
+    /// `CMAKE_SYSTEM_NAME`
+    system_name: String,
+    /// `CMAKE_SYSTEM_PROCESSOR`
+    system_processor: String,
+    /// `CMAKE_SYSTEM_VERSION`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    system_version: Option<String>,
+    /// CPU passed as `-mcpu`, like `cortex-m4`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    cpu: Option<String>,
+    /// FPU passed as `-mfpu`, like `fpv4-sp-d16`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    fpu: Option<String>,
+    /// Float ABI passed as `-mfloat-abi`, like `hard`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    float_abi: Option<String>,
     /// `CMAKE_SYSROOT`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     sysroot: Option<PathBuf>,
+    /// Also pass the sysroot as a `--sysroot` compiler and linker flag
+    sysroot_as_flag: bool,
+    /// Link executables statically for musl targets
+    musl_static: bool,
+    /// Sysroot of the musl cross toolchain, added to `CMAKE_FIND_ROOT_PATH`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    musl_root: Option<PathBuf>,
     /// `CMAKE_C_COMPILER`
     cc: PathBuf,
     /// `CMAKE_CXX_COMPILER`
     cxx: PathBuf,
+    /// Arguments of a `CC` value like `clang -m32`, passed as C flags
+    cc_args: Vec<String>,
+    /// Arguments of a `CXX` value like `clang++ -m32`, passed as C++ flags
+    cxx_args: Vec<String>,
+    /// `CMAKE_Fortran_COMPILER`, only set when a Fortran compiler is found
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    fortran: Option<PathBuf>,
+    /// `CMAKE_<LANG>_COMPILER_LAUNCHER`, like `ccache` or `sccache`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    compiler_launcher: Option<PathBuf>,
+    /// `CMAKE_ASM_COMPILER`, defaults to the C compiler
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    asm: Option<PathBuf>,
+    /// Family of the C compiler
+    compiler_family: CompilerFamily,
+    /// Whether the compiler family was set explicitly, it's derived from the C compiler
+    /// otherwise
+    #[cfg_attr(feature = "serde", serde(skip))]
+    compiler_family_explicit: bool,
+    /// `CMAKE_<LANG>_COMPILER_TARGET`, the LLVM target triple of clang
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    compiler_target: Option<String>,
+    /// `CMAKE_C_FLAGS_INIT`
+    c_flags: Vec<String>,
+    /// `CMAKE_CXX_FLAGS_INIT`
+    cxx_flags: Vec<String>,
+    /// `CMAKE_EXE_LINKER_FLAGS_INIT`
+    exe_linker_flags: Vec<String>,
+    /// `CMAKE_SHARED_LINKER_FLAGS_INIT`
+    shared_linker_flags: Vec<String>,
+    /// `CMAKE_MODULE_LINKER_FLAGS_INIT`
+    module_linker_flags: Vec<String>,
     /// `CMAKE_AR`
     ar: PathBuf,
     /// `CMAKE_RANLIB`
-    ranlib: PathBuf,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    ranlib: Option<PathBuf>,
+    /// `CMAKE_STRIP`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    strip: Option<PathBuf>,
+    /// `CMAKE_NM`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    nm: Option<PathBuf>,
+    /// `CMAKE_OBJCOPY`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    objcopy: Option<PathBuf>,
+    /// `CMAKE_OBJDUMP`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    objdump: Option<PathBuf>,
+    /// `CMAKE_RC_COMPILER`, only used for windows-gnu targets
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    rc: Option<PathBuf>,
+    /// `CMAKE_LINKER`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    linker: Option<PathBuf>,
+    /// `CMAKE_MT`, the manifest tool of MSVC toolchains
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    mt: Option<PathBuf>,
+    /// `CMAKE_CROSSCOMPILING_EMULATOR`, the command running target programs
+    emulator: Vec<String>,
+    /// Run target programs with `qemu-<arch> -L <sysroot>` when no emulator is set
+    auto_emulator: bool,
+    /// Emit `CMAKE_AR` and `CMAKE_RANLIB`, instead of letting CMake find them
+    emit_archiver: bool,
+    /// `CMAKE_<LANG>_ARCHIVE_CREATE`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    archive_create: Option<String>,
+    /// `CMAKE_<LANG>_ARCHIVE_APPEND`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    archive_append: Option<String>,
+    /// `CMAKE_<LANG>_ARCHIVE_FINISH`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    archive_finish: Option<String>,
+    /// Skip the rpath of the build tree, `None` to skip it when cross compiling
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    skip_rpath: Option<bool>,
+    /// `CMAKE_INSTALL_RPATH`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    install_rpath: Option<String>,
+    /// Android API level
+    android_api_level: u32,
+    /// `CMAKE_OSX_SYSROOT`, the SDK from `SDKROOT` or reported by `xcrun` for Apple targets
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    osx_sysroot: Option<PathBuf>,
+    /// `CMAKE_OSX_DEPLOYMENT_TARGET`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    osx_deployment_target: Option<String>,
+    /// Emscripten installation providing `Emscripten.cmake`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    emscripten_root: Option<PathBuf>,
+    /// `CMAKE_STAGING_PREFIX`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    staging_prefix: Option<PathBuf>,
+    /// `CMAKE_INSTALL_PREFIX`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    install_prefix: Option<PathBuf>,
+    /// `CMAKE_FIND_ROOT_PATH`, in addition to the sysroot
+    find_root_path: Vec<PathBuf>,
+    /// `CMAKE_FIND_ROOT_PATH_MODE_PROGRAM`
+    find_root_path_mode_program: FindRootPathMode,
+    /// `CMAKE_FIND_ROOT_PATH_MODE_LIBRARY`
+    find_root_path_mode_library: FindRootPathMode,
+    /// `CMAKE_FIND_ROOT_PATH_MODE_INCLUDE`
+    find_root_path_mode_include: FindRootPathMode,
+    /// `CMAKE_FIND_ROOT_PATH_MODE_PACKAGE`
+    find_root_path_mode_package: FindRootPathMode,
+    /// `CMAKE_TRY_COMPILE_TARGET_TYPE`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    try_compile_target_type: Option<TryCompileTargetType>,
+    /// `CMAKE_EXECUTABLE_SUFFIX`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    executable_suffix: Option<String>,
+    /// `CMAKE_STATIC_LIBRARY_SUFFIX`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    static_library_suffix: Option<String>,
+    /// `CMAKE_SHARED_LIBRARY_SUFFIX`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    shared_library_suffix: Option<String>,
+    /// `CMAKE_C_STANDARD`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    c_standard: Option<u32>,
+    /// `CMAKE_C_STANDARD_REQUIRED`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    c_standard_required: Option<bool>,
+    /// `CMAKE_C_EXTENSIONS`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    c_extensions: Option<bool>,
+    /// `CMAKE_CXX_STANDARD`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    cxx_standard: Option<u32>,
+    /// `CMAKE_CXX_STANDARD_REQUIRED`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    cxx_standard_required: Option<bool>,
+    /// `CMAKE_CXX_EXTENSIONS`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    cxx_extensions: Option<bool>,
+    /// `CMAKE_POSITION_INDEPENDENT_CODE`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    position_independent_code: Option<bool>,
+    /// `CMAKE_INTERPROCEDURAL_OPTIMIZATION`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    interprocedural_optimization: Option<bool>,
+    /// `CMAKE_VERBOSE_MAKEFILE`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    verbose_makefile: Option<bool>,
+    /// Skip the compiler identification of CMake, assuming the detected compiler family
+    assume_compiler_id: bool,
+    /// Comment added to the header of the toolchain file
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    header_comment: Option<String>,
+    /// Stamp the toolchain file with the time it was generated
+    timestamp: bool,
+    /// Oldest CMake version the toolchain file has to work with
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    min_cmake_version: Option<String>,
+    /// CMake generator, like `Ninja`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    generator: Option<String>,
+    /// Files included before the generated settings
+    include_before: Vec<PathBuf>,
+    /// Files included after the generated settings
+    include_after: Vec<PathBuf>,
+    /// Separate CMake file for the compiler and linker flags
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    flags_file: Option<PathBuf>,
+    /// Custom CMake variables
+    defines: BTreeMap<String, String>,
+    /// How each tool was resolved
+    sources: BTreeMap<String, ToolSource>,
+    /// Use the LLVM binutils like `llvm-ar`, `None` to use them for clang when available
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    llvm_binutils: Option<bool>,
+    /// Use `zig cc` as the compiler and `zig ar` as the archiver
+    use_zig: bool,
+    /// Directory searched for the binutils, defaults to the directory of the C compiler
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    tool_search_dir: Option<PathBuf>,
+    /// Cross compile prefix set explicitly, like `aarch64-linux-gnu`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    prefix_override: Option<String>,
+    /// Executable suffixes tried when looking for the cross compiler on `PATH`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    path_exe_suffixes: Option<Vec<String>>,
+    /// Toolchain installation whose `bin` directory is searched before `PATH`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    toolchain_root: Option<PathBuf>,
+    /// Cached cross compile prefix of the target, looking it up may scan `PATH`
+    #[cfg_attr(feature = "serde", serde(skip))]
+    prefix: OnceLock<Option<String>>,
+    /// Environment variables consulted so far
+    #[cfg_attr(feature = "serde", serde(skip))]
+    consulted_env: ConsultedEnv,
+    /// User provided `cc::Build` the compilers are detected with
+    #[cfg_attr(feature = "serde", serde(skip))]
+    cc_build: Option<cc::Build>,
+    /// Detect the compilers with the cc crate, instead of guessing them from the target
+    probe_build: bool,
+    /// C compiler detected by the cc crate
+    #[cfg_attr(feature = "serde", serde(skip))]
+    cc_tool: Option<cc::Tool>,
+    /// C++ compiler detected by the cc crate
+    #[cfg_attr(feature = "serde", serde(skip))]
+    cxx_tool: Option<cc::Tool>,
+    /// Time spent detecting the compilers with the cc crate
+    #[cfg_attr(feature = "serde", serde(skip))]
+    probe_duration: Option<Duration>,
+    /// Host and target the compilers were detected for, they're only detected again when
+    /// either changes
+    #[cfg_attr(feature = "serde", serde(skip))]
+    probed_for: Option<(String, String)>,
+    /// Why the compilers couldn't be detected after the last change of a setting they depend
+    /// on, reported by [`CMakeToolchain::validate`]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    resolve_error: Option<cc::Error>,
 }
 
 impl CMakeToolchain {
+    /// Create a new toolchain for `target`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the toolchain can't be resolved, see [`CMakeToolchain::try_new`]
+    /// for a non-panicking version.
     pub fn new(target: &str) -> Self {
-        let version_meta = rustc_version::version_meta().unwrap();
-        let mut c_cfg = cc::Build::new();
-        c_cfg
-            // opt_level, host and target are required
-            .host(&version_meta.host)
-            .target(target)
-            .opt_level(0)
-            // Suppress cargo metadata for example env vars printing
-            .cargo_metadata(false)
-            .cpp(false)
-            .debug(false)
-            .warnings(false);
-        let c_compiler = c_cfg.get_compiler();
+        Self::try_new(target).unwrap_or_else(|err| panic!("{}", err))
+    }
 
-        let mut cxx_cfg = c_cfg.clone();
-        cxx_cfg.cpp(true);
-        let cxx_compiler = c_cfg.get_compiler();
+    /// Create a new toolchain for the `TARGET` of the running build script
+    pub fn from_env() -> Result<Self, Error> {
+        let target = env::var("TARGET").map_err(|source| Error::TargetNotSet { source })?;
+        Self::try_new(&target)
+    }
+
+    /// Create a new toolchain for `target`
+    ///
+    /// Tools are read from environment variables like the cc crate does, in order of
+    /// precedence `CC_<target>`, `CC_<target_with_underscores>`, `TARGET_CC` or `HOST_CC`
+    /// depending on whether cross compiling, and `CC`. The cc crate's guess is the fallback.
+    pub fn try_new(target: &str) -> Result<Self, Error> {
+        Self::try_new_with(target, None, true)
+    }
+
+    /// Create a new toolchain for `target` with the recommended settings for its kind of target
+    ///
+    /// On top of [`CMakeToolchain::try_new`], which already derives the system name and
+    /// processor, the default find root path modes and a static library `try_compile` for
+    /// bare metal targets, this
+    ///
+    /// * detects the sysroot of the cross compiler when none is configured, see
+    ///   [`CMakeToolchain::detect_sysroot`]
+    /// * runs cross compiled test executables of Linux targets with qemu user mode emulation,
+    ///   see [`CMakeToolchain::auto_emulator`]
+    /// * builds position independent code like Rust does, except for bare metal, wasm and
+    ///   Windows targets
+    ///
+    /// Everything can still be changed with the setters afterwards.
+    pub fn recommended(target: &str) -> Result<Self, Error> {
+        let mut toolchain = Self::try_new(target)?;
+        if toolchain.is_cross_compiling() {
+            if toolchain.sysroot.is_none() {
+                toolchain.detect_sysroot();
+            }
+            if toolchain.system_name == "Linux" && qemu_arch(target).is_some() {
+                toolchain.auto_emulator(true);
+            }
+        }
+        let pic = toolchain.system_name != "Generic"
+            && !target.starts_with("wasm")
+            && !target.contains("windows");
+        if pic {
+            toolchain.position_independent_code(true);
+        }
+        Ok(toolchain)
+    }
+
+    /// Create a new toolchain for `target` without probing the compilers with the cc crate
+    ///
+    /// The compilers are taken from the environment or guessed from the cross compile
+    /// prefix of the target, which is cheaper when resolving toolchains for many targets.
+    pub fn new_without_probe(target: &str) -> Self {
+        Self::try_new_without_probe(target).unwrap_or_else(|err| panic!("{}", err))
+    }
 
+    /// Create a new toolchain for `target` without probing the compilers with the cc crate
+    pub fn try_new_without_probe(target: &str) -> Result<Self, Error> {
+        Self::try_new_with(target, None, false)
+    }
+
+    /// Create a new toolchain for `target`, detecting the compilers with `build`
+    ///
+    /// This guarantees the toolchain uses the same compilers as `build` does. The optimization,
+    /// debug info and warning flags of `build` are only used for probing, they're never
+    /// emitted to the toolchain file.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the toolchain can't be resolved, see [`CMakeToolchain::try_from_cc_build`]
+    /// for a non-panicking version.
+    pub fn from_cc_build(target: &str, build: &cc::Build) -> Self {
+        Self::try_from_cc_build(target, build).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Create a new toolchain for `target`, detecting the compilers with `build`
+    pub fn try_from_cc_build(target: &str, build: &cc::Build) -> Result<Self, Error> {
+        Self::try_new_with(target, Some(build.clone()), true)
+    }
+
+    /// Check whether a working C compiler is installed for `target`
+    ///
+    /// The toolchain is resolved without probing, and the C compiler has to exist and run
+    /// `--version` successfully. This never panics, so build scripts can call it to choose
+    /// between a CMake build and a fallback.
+    pub fn is_available(target: &str) -> bool {
+        let toolchain = match Self::try_new_without_probe(target) {
+            Ok(toolchain) => toolchain,
+            Err(_) => return false,
+        };
+        let cc = match find_program(&toolchain.cc, &toolchain.search_paths()) {
+            Some(cc) => cc,
+            None => return false,
+        };
+        // `cl.exe` has no `--version`, it prints its version with any argument
+        if toolchain.compiler_family == CompilerFamily::Msvc {
+            return true;
+        }
+        let mut command = Command::new(cc);
+        if is_zig(&toolchain.cc) {
+            command.arg("cc");
+        }
+        command
+            .args(&toolchain.cc_args)
+            .arg("--version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+    }
+
+    fn try_new_with(
+        target: &str,
+        cc_build: Option<cc::Build>,
+        probe_build: bool,
+    ) -> Result<Self, Error> {
+        let version_meta = rustc_version::version_meta().map_err(|source| Error::RustcVersion {
+            target: target.to_string(),
+            source,
+        })?;
         let mut toolchain = Self {
             host: version_meta.host,
             target: target.to_string(),
+            system_name: String::new(),
+            system_processor: String::new(),
+            system_version: None,
+            cpu: None,
+            fpu: None,
+            float_abi: None,
             sysroot: None,
-            cc: c_compiler.path().to_path_buf(),
-            cxx: cxx_compiler.path().to_path_buf(),
+            sysroot_as_flag: false,
+            musl_static: true,
+            musl_root: None,
+            cc: PathBuf::new(),
+            cxx: PathBuf::new(),
+            cc_args: Vec::new(),
+            cxx_args: Vec::new(),
+            fortran: None,
+            compiler_launcher: None,
+            asm: None,
+            compiler_family: CompilerFamily::Gnu,
+            compiler_family_explicit: false,
+            compiler_target: None,
+            c_flags: Vec::new(),
+            cxx_flags: Vec::new(),
+            exe_linker_flags: Vec::new(),
+            shared_linker_flags: Vec::new(),
+            module_linker_flags: Vec::new(),
             ar: "ar".into(),
-            ranlib: "ranlib".into(),
+            ranlib: None,
+            strip: None,
+            nm: None,
+            objcopy: None,
+            objdump: None,
+            rc: None,
+            linker: None,
+            mt: None,
+            emulator: Vec::new(),
+            auto_emulator: false,
+            emit_archiver: true,
+            archive_create: None,
+            archive_append: None,
+            archive_finish: None,
+            skip_rpath: None,
+            install_rpath: None,
+            android_api_level: 21,
+            osx_sysroot: None,
+            osx_deployment_target: None,
+            emscripten_root: None,
+            staging_prefix: None,
+            install_prefix: None,
+            find_root_path: Vec::new(),
+            find_root_path_mode_program: FindRootPathMode::Never,
+            find_root_path_mode_library: FindRootPathMode::Only,
+            find_root_path_mode_include: FindRootPathMode::Only,
+            find_root_path_mode_package: FindRootPathMode::Only,
+            try_compile_target_type: None,
+            executable_suffix: None,
+            static_library_suffix: None,
+            shared_library_suffix: None,
+            c_standard: None,
+            c_standard_required: None,
+            c_extensions: None,
+            cxx_standard: None,
+            cxx_standard_required: None,
+            cxx_extensions: None,
+            position_independent_code: None,
+            interprocedural_optimization: None,
+            verbose_makefile: None,
+            assume_compiler_id: false,
+            header_comment: None,
+            timestamp: false,
+            min_cmake_version: None,
+            generator: None,
+            include_before: Vec::new(),
+            include_after: Vec::new(),
+            flags_file: None,
+            defines: BTreeMap::new(),
+            sources: BTreeMap::new(),
+            use_zig: false,
+            llvm_binutils: None,
+            tool_search_dir: None,
+            prefix_override: None,
+            path_exe_suffixes: None,
+            toolchain_root: None,
+            prefix: OnceLock::new(),
+            consulted_env: ConsultedEnv::default(),
+            cc_build,
+            probe_build,
+            cc_tool: None,
+            cxx_tool: None,
+            probe_duration: None,
+            probed_for: None,
+            resolve_error: None,
         };
-        let ar = toolchain.find_ar();
-        toolchain.ar = ar;
-        toolchain
+        toolchain.compiler_launcher = toolchain
+            .get_var("CMAKE_COMPILER_LAUNCHER")
+            .or_else(|| toolchain.getenv("RUSTC_WRAPPER"))
+            .map(PathBuf::from);
+        toolchain.c_flags = toolchain.find_flags("CFLAGS");
+        toolchain.cxx_flags = toolchain.find_flags("CXXFLAGS");
+        let linker_flags = toolchain.find_flags("LDFLAGS");
+        toolchain.exe_linker_flags = linker_flags.clone();
+        toolchain.shared_linker_flags = linker_flags.clone();
+        toolchain.module_linker_flags = linker_flags;
+        toolchain.resolve_target();
+        toolchain.resolve_tools()?;
+        Ok(toolchain)
     }
 
-    /// Set CMake sysroot
-    pub fn sysroot(&mut self, sysroot: PathBuf) -> &mut Self {
-        self.sysroot = Some(sysroot);
+    /// Derive the system settings of the target
+    fn resolve_target(&mut self) {
+        self.system_name = self.find_system_name();
+        self.try_compile_target_type = if self.system_name == "Generic" {
+            // Bare metal targets can't link executables without a linker script, and the
+            // `wasm*-unknown-unknown` targets have no C library to link against
+            Some(TryCompileTargetType::StaticLibrary)
+        } else {
+            None
+        };
+        let (cpu, fpu, float_abi) = match cortex_m_defaults(&self.target) {
+            Some((cpu, fpu, float_abi)) => (Some(cpu), fpu, float_abi),
+            None => (None, None, None),
+        };
+        self.cpu = cpu.map(String::from);
+        self.fpu = fpu.map(String::from);
+        self.float_abi = float_abi.map(String::from);
+        if mips_soft_float(&self.target) {
+            self.float_abi = Some("soft".to_string());
+        }
+        // Emscripten.cmake picks its own suffixes
+        let wasm = self.target.starts_with("wasm") && !self.target.contains("emscripten");
+        self.executable_suffix = if wasm {
+            Some(".wasm".to_string())
+        } else if self.target.contains("-none") {
+            // Bare metal images are ELF files
+            Some(".elf".to_string())
+        } else {
+            None
+        };
+        self.system_processor = match &self.cpu {
+            Some(cpu) => cpu.clone(),
+            None => self.find_system_processor(),
+        };
+        self.system_version = if self.target.contains("android") {
+            Some(self.android_api_level.to_string())
+        } else {
+            None
+        };
+        self.emscripten_root = None;
+        self.osx_sysroot = self.find_osx_sysroot();
+        self.osx_deployment_target =
+            apple_deployment_target_var(&self.target).and_then(|var| self.getenv(var));
+    }
+
+    /// Resolve the compilers and binutils that weren't set explicitly
+    ///
+    /// Both the cc crate and the `HOST_`/`TARGET_` environment variable precedence depend on
+    /// the host, so this runs again whenever the host changes. Nothing is changed when the
+    /// compilers can't be detected.
+    fn resolve_tools(&mut self) -> Result<(), Error> {
+        self.prefix = OnceLock::new();
+        let (c_compiler, cxx_compiler, compiler_family) = if self.probe_build {
+            self.probe_compilers()?
+        } else {
+            self.guess_compilers()
+        };
+        let (c_compiler, cxx_compiler) = if self.is_cross_compiling() {
+            versioned_compilers(c_compiler, cxx_compiler, &self.search_paths())
+        } else {
+            (c_compiler, cxx_compiler)
+        };
+        let c_compiler = self.in_toolchain_root(c_compiler);
+        let cxx_compiler = self.in_toolchain_root(cxx_compiler);
+        let compiler_source = if self.probe_build {
+            ToolSource::Detected
+        } else {
+            ToolSource::Default
+        };
+        if !self.is_explicit("cc") {
+            self.cc = c_compiler;
+            self.set_source("cc", compiler_source.clone());
+            self.cc_args.clear();
+            // Explicit `CC`/`CXX` values take precedence over the cc crate's guess
+            if let Some((var, cc)) = self.find_var("CC") {
+                let (launcher, cc, args) = split_compiler(&cc, "cc");
+                self.cc = cc;
+                self.cc_args = args;
+                if launcher.is_some() {
+                    self.compiler_launcher = launcher;
+                }
+                self.set_source("cc", ToolSource::Env(var));
+            }
+            self.derive_compiler_family(compiler_family);
+        }
+        if !self.is_explicit("cxx") {
+            self.cxx = cxx_compiler;
+            self.set_source("cxx", compiler_source);
+            self.cxx_args.clear();
+            if let Some((var, cxx)) = self.find_var("CXX") {
+                let (launcher, cxx, args) = split_compiler(&cxx, "c++");
+                self.cxx = cxx;
+                self.cxx_args = args;
+                if launcher.is_some() {
+                    self.compiler_launcher = launcher;
+                }
+                self.set_source("cxx", ToolSource::Env(var));
+            }
+        }
+        if !self.is_explicit("fortran") {
+            let fortran = self.find_fortran();
+            self.fortran = self.resolved("fortran", fortran);
+        }
+        if !self.is_explicit("ar") {
+            let (ar, source) = self.find_ar();
+            self.ar = match source {
+                ToolSource::Env(_) => ar,
+                _ => self.in_toolchain_root(ar),
+            };
+            self.set_source("ar", source);
+        }
+        if !self.is_explicit("ranlib") {
+            let ranlib = self.find_ranlib();
+            self.ranlib = self.resolved("ranlib", ranlib);
+        }
+        if !self.is_explicit("strip") {
+            let strip = self.find_binutil("STRIP", "strip");
+            self.strip = self.resolved("strip", strip);
+        }
+        if !self.is_explicit("nm") {
+            let nm = self.find_binutil("NM", "nm");
+            self.nm = self.resolved("nm", nm);
+        }
+        if !self.is_explicit("objcopy") {
+            let objcopy = self.find_binutil("OBJCOPY", "objcopy");
+            self.objcopy = self.resolved("objcopy", objcopy);
+        }
+        if !self.is_explicit("objdump") {
+            let objdump = self.find_binutil("OBJDUMP", "objdump");
+            self.objdump = self.resolved("objdump", objdump);
+        }
+        if !self.is_explicit("rc") {
+            let rc = if self.target.contains("windows-gnu") {
+                self.find_rc()
+            } else {
+                None
+            };
+            self.rc = self.resolved("rc", rc);
+        }
+        if !self.is_explicit("linker") {
+            let linker = self.find_linker();
+            self.linker = self.resolved("linker", linker);
+        }
+        if !self.is_explicit("mt") {
+            let mt = self.find_msvc_tool("MT", "mt.exe");
+            self.mt = self.resolved("mt", mt);
+        }
+        self.find_android_ndk_tools();
+        self.find_ohos_sdk_tools();
+        self.find_emscripten_tools();
+        self.find_wasi_sdk_tools();
+        self.find_zig_tools();
+        self.musl_root = self.find_musl_root();
+        Ok(())
+    }
+
+    /// Use `zig` for the compilers and archiver, when enabled or set through `CC`
+    fn find_zig_tools(&mut self) {
+        let zig_source = ToolSource::Sdk("Zig".to_string());
+        if self.use_zig {
+            if self.is_default("cc", "CC") {
+                self.cc = "zig".into();
+                self.cc_args.clear();
+                self.set_source("cc", zig_source.clone());
+            }
+            if self.is_default("cxx", "CXX") {
+                self.cxx = "zig".into();
+                self.cxx_args.clear();
+                self.set_source("cxx", zig_source.clone());
+            }
+        }
+        if !is_zig(&self.cc) {
+            return;
+        }
+        let zig = self.cc.clone();
+        if self.is_default("ar", "AR") {
+            self.ar = zig.clone();
+            self.set_source("ar", zig_source.clone());
+        }
+        if self.is_default("ranlib", "RANLIB") {
+            self.ranlib = Some(zig);
+            self.set_source("ranlib", zig_source);
+        }
+        self.compiler_family = CompilerFamily::Clang;
+        // Zig takes its own target names through `-target`
+        self.compiler_target = None;
+    }
+
+    /// Detect the C and C++ compilers with the cc crate
+    fn probe_compilers(&mut self) -> Result<(PathBuf, PathBuf, Option<CompilerFamily>), Error> {
+        let probe_key = (self.host.clone(), self.target.clone());
+        if let (Some(cc), Some(cxx)) = (&self.cc_tool, &self.cxx_tool) {
+            if self.probed_for.as_ref() == Some(&probe_key) {
+                let family = CompilerFamily::from_tool(cc);
+                return Ok((cc.path().into(), cxx.path().into(), Some(family)));
+            }
+        }
+        let started = Instant::now();
+        let mut c_cfg = match &self.cc_build {
+            Some(build) => build.clone(),
+            None => {
+                // Match the Cargo profile in build scripts, opt_level is required otherwise
+                let opt_level = self.getenv("OPT_LEVEL").unwrap_or_else(|| "0".to_string());
+                let debug = self
+                    .getenv("DEBUG")
+                    .is_some_and(|debug| debug != "false" && debug != "0");
+                let mut build = cc::Build::new();
+                build.opt_level_str(&opt_level).debug(debug).warnings(false);
+                build
+            }
+        };
+        c_cfg
+            // host and target are required
+            .host(&self.host)
+            .target(&self.target)
+            // Suppress cargo metadata for example env vars printing
+            .cargo_metadata(false)
+            .cpp(false);
+        let compiler_not_found = |source| Error::CompilerNotFound {
+            target: self.target.clone(),
+            source,
+        };
+        let c_compiler = c_cfg.try_get_compiler().map_err(compiler_not_found)?;
+
+        let mut cxx_cfg = c_cfg.clone();
+        cxx_cfg.cpp(true);
+        let cxx_compiler = cxx_cfg.try_get_compiler().map_err(compiler_not_found)?;
+
+        // Ask the registry for the `cl.exe` of the target architecture, the one on `PATH`
+        // may be the host's when cross compiling for example from x64 to arm64
+        let cl = if self.target.contains("msvc") {
+            cc::windows_registry::find_tool(&self.target, "cl.exe")
+        } else {
+            None
+        };
+        let c_compiler = cl.clone().unwrap_or(c_compiler);
+        let cxx_compiler = cl.unwrap_or(cxx_compiler);
+        self.probe_duration = Some(started.elapsed());
+        let compilers = (
+            c_compiler.path().to_path_buf(),
+            cxx_compiler.path().to_path_buf(),
+            Some(CompilerFamily::from_tool(&c_compiler)),
+        );
+        self.cc_tool = Some(c_compiler);
+        self.cxx_tool = Some(cxx_compiler);
+        self.probed_for = Some(probe_key);
+        Ok(compilers)
+    }
+
+    /// Guess the C and C++ compiler names from the target without the cc crate
+    fn guess_compilers(&self) -> (PathBuf, PathBuf, Option<CompilerFamily>) {
+        let target = &self.target;
+        let (cc, cxx) = if target.contains("msvc") {
+            ("cl.exe".to_string(), "cl.exe".to_string())
+        } else if target.contains("android") {
+            let clang = format!(
+                "{}{}-clang",
+                android_ndk_prefixes(target).0,
+                self.android_api_level
+            );
+            (clang.clone(), format!("{}++", clang))
+        } else if target.contains("-apple-") {
+            ("clang".to_string(), "clang++".to_string())
+        } else if target.ends_with("-ohos") {
+            // The OpenHarmony SDK only ships clang, the target is passed as `compiler_target`
+            ("clang".to_string(), "clang++".to_string())
+        } else if is_bare_wasm(target) {
+            // Only clang targets wasm without a libc, there is no cross compile prefix
+            ("clang".to_string(), "clang++".to_string())
+        } else if target.ends_with("-gnullvm") {
+            // llvm-mingw names its compilers like `aarch64-w64-mingw32-clang`
+            match self.prefix().filter(|_| self.is_cross_compiling()) {
+                Some(prefix) => (format!("{}-clang", prefix), format!("{}-clang++", prefix)),
+                None => ("clang".to_string(), "clang++".to_string()),
+            }
+        } else if self.is_cross_compiling() {
+            match self.prefix() {
+                Some(prefix) => (format!("{}-gcc", prefix), format!("{}-g++", prefix)),
+                None => ("cc".to_string(), "c++".to_string()),
+            }
+        } else {
+            ("cc".to_string(), "c++".to_string())
+        };
+        (cc.into(), cxx.into(), None)
+    }
+
+    /// Apply `update` to a setting the tools depend on and resolve the tools again
+    ///
+    /// The setting is kept when the compilers can't be detected afterwards, the previous
+    /// tools are kept and the failure is reported by [`CMakeToolchain::validate`].
+    fn update_tools(&mut self, update: impl FnOnce(&mut Self)) -> &mut Self {
+        update(self);
+        self.resolve_error = match self.resolve_tools() {
+            Err(Error::CompilerNotFound { source, .. }) => Some(source),
+            _ => None,
+        };
         self
     }
 
-    /// Get CMake sysroot
-    pub fn get_sysroot(&self) -> Option<&Path> {
-        self.sysroot.as_deref()
+    /// Like [`CMakeToolchain::update_tools`], but nothing is changed when the compilers can't
+    /// be detected afterwards
+    fn try_update_tools(&mut self, update: impl FnOnce(&mut Self)) -> Result<&mut Self, Error> {
+        let previous = self.clone();
+        self.update_tools(update);
+        if let Some(source) = self.resolve_error.take() {
+            let err = Error::CompilerNotFound {
+                target: self.target.clone(),
+                source,
+            };
+            *self = previous;
+            return Err(err);
+        }
+        Ok(self)
     }
 
-    /// Set C compiler path
-    pub fn cc(&mut self, cc: PathBuf) -> &mut Self {
-        self.cc = cc;
+    /// Set the host target, defaults to the host of the running rustc
+    ///
+    /// The host decides whether `HOST_` or `TARGET_` environment variables are consulted and
+    /// whether a cross compile prefix is used, so tools that weren't set explicitly are
+    /// resolved again. The previous tools are kept when no compiler is found for the new host,
+    /// [`CMakeToolchain::validate`] reports it, see [`CMakeToolchain::try_host`] to fail
+    /// right away.
+    pub fn host(&mut self, host: String) -> &mut Self {
+        self.update_tools(|toolchain| toolchain.host = host)
+    }
+
+    /// Set the host target like [`CMakeToolchain::host`], nothing is changed when no compiler
+    /// is found for the new host
+    pub fn try_host(&mut self, host: String) -> Result<&mut Self, Error> {
+        self.try_update_tools(|toolchain| toolchain.host = host)
+    }
+
+    /// Get the C compiler detected by the cc crate, `None` if not probed
+    ///
+    /// The [`cc::Tool`] knows the arguments and environment the cc crate invokes the
+    /// compiler with. It may differ from [`CMakeToolchain::get_cc`] when the C compiler
+    /// was set explicitly.
+    pub fn get_cc_tool(&self) -> Option<&cc::Tool> {
+        self.cc_tool.as_ref()
+    }
+
+    /// Get the C++ compiler detected by the cc crate, `None` if not probed
+    pub fn get_cxx_tool(&self) -> Option<&cc::Tool> {
+        self.cxx_tool.as_ref()
+    }
+
+    /// Get the time spent probing the compilers with the cc crate, `None` if not probed
+    pub fn get_probe_duration(&self) -> Option<Duration> {
+        self.probe_duration
+    }
+
+    /// Get the host target
+    pub fn get_host(&self) -> &str {
+        &self.host
+    }
+
+    /// Set the build target
+    ///
+    /// The system settings of the target are derived again, replacing values set before, and
+    /// tools that weren't set explicitly are resolved again. The previous tools are kept when
+    /// no compiler is found for the new target, [`CMakeToolchain::validate`] reports it, see
+    /// [`CMakeToolchain::try_target`] to fail right away.
+    pub fn target(&mut self, target: String) -> &mut Self {
+        self.update_tools(|toolchain| {
+            toolchain.target = target;
+            toolchain.resolve_target();
+        })
+    }
+
+    /// Set the build target like [`CMakeToolchain::target`], nothing is changed when no
+    /// compiler is found for the new target
+    pub fn try_target(&mut self, target: String) -> Result<&mut Self, Error> {
+        self.try_update_tools(|toolchain| {
+            toolchain.target = target;
+            toolchain.resolve_target();
+        })
+    }
+
+    /// Get the build target
+    pub fn get_target(&self) -> &str {
+        &self.target
+    }
+
+    /// Whether the target differs from the host
+    ///
+    /// The system settings and `CMAKE_FIND_ROOT_PATH` are only emitted when cross compiling,
+    /// since they would put CMake into cross compiling mode for a native build.
+    pub fn is_cross_compiling(&self) -> bool {
+        self.host != self.target
+    }
+
+    /// Set CMake system name
+    pub fn system_name(&mut self, system_name: String) -> &mut Self {
+        self.system_name = system_name;
         self
     }
 
-    /// Get C compiler path
-    pub fn get_cc(&self) -> &Path {
-        &self.cc
+    /// Get CMake system name
+    pub fn get_system_name(&self) -> &str {
+        &self.system_name
     }
 
-    /// Set C++ compiler path
-    pub fn cxx(&mut self, cxx: PathBuf) -> &mut Self {
-        self.cxx = cxx;
+    /// Set CMake system processor
+    pub fn system_processor(&mut self, system_processor: String) -> &mut Self {
+        self.system_processor = system_processor;
         self
     }
 
-    /// Get C++ compiler path
-    pub fn get_cxx(&self) -> &Path {
-        &self.cxx
+    /// Get CMake system processor
+    pub fn get_system_processor(&self) -> &str {
+        &self.system_processor
     }
 
-    /// Set archiver path
-    pub fn ar(&mut self, ar: PathBuf) -> &mut Self {
-        self.ar = ar;
+    /// Set the CPU passed as `-mcpu=<cpu>` to the C, C++ and assembler compilers
+    ///
+    /// MIPS compilers get `-march=<cpu>` instead. For ARM and thumb targets this also sets the
+    /// system processor to the CPU, other architectures keep the processor CMake expects.
+    /// Defaults to the core of Cortex-M targets, like `cortex-m4` for `thumbv7em-none-eabihf`.
+    pub fn cpu(&mut self, cpu: String) -> &mut Self {
+        if self.target.starts_with("arm") || self.target.starts_with("thumb") {
+            self.system_processor = cpu.clone();
+        }
+        self.cpu = Some(cpu);
         self
     }
 
-    /// Get archiver path
-    pub fn get_ar(&self) -> &Path {
-        &self.ar
+    /// Get the CPU passed as `-mcpu`
+    pub fn get_cpu(&self) -> Option<&str> {
+        self.cpu.as_deref()
     }
 
-    /// Set ranlib path
-    pub fn ranlib(&mut self, ranlib: PathBuf) -> &mut Self {
-        self.ranlib = ranlib;
+    /// Set the FPU passed as `-mfpu=<fpu>`, defaults to the FPU of hard float Cortex-M targets
+    pub fn fpu(&mut self, fpu: String) -> &mut Self {
+        self.fpu = Some(fpu);
+        self
+    }
+
+    /// Get the FPU passed as `-mfpu`
+    pub fn get_fpu(&self) -> Option<&str> {
+        self.fpu.as_deref()
+    }
+
+    /// Set the float ABI passed as `-mfloat-abi=<abi>`, like `soft`, `softfp` or `hard`
+    ///
+    /// MIPS compilers get `-msoft-float` or `-mhard-float` instead. Defaults to `hard` for
+    /// hard float Cortex-M targets and `soft` for soft float MIPS targets like
+    /// `mipsel-unknown-linux-musl`.
+    pub fn float_abi(&mut self, float_abi: String) -> &mut Self {
+        self.float_abi = Some(float_abi);
         self
     }
 
-    /// Get ranlib path
-    pub fn get_ranlib(&self) -> &Path {
-        &self.ranlib
+    /// Get the float ABI passed as `-mfloat-abi`
+    pub fn get_float_abi(&self) -> Option<&str> {
+        self.float_abi.as_deref()
     }
 
-    fn find_ar(&self) -> PathBuf {
-        if let Some(p) = self.get_var("AR") {
-            return p.into();
+    /// Flags selecting the instruction set, CPU and floating point ABI
+    fn arch_flags(&self) -> Vec<String> {
+        let mut flags = Vec::new();
+        if self.is_x86_multilib() {
+            flags.push("-m32".to_string());
         }
-        let target = &self.target;
-        let default_ar = "ar".to_string();
-        let program = if target.contains("android") {
-            format!("{}-ar", target.replace("armv7", "arm"))
-        } else if target.contains("emscripten") {
-            "emar".to_string()
-        } else if target.contains("msvc") {
-            match cc::windows_registry::find(&target, "lib.exe") {
-                // FIXME
-                // Some(t) => return Ok((t, "lib.exe".to_string())),
-                Some(_) => "lib.exe".to_string(),
-                None => "lib.exe".to_string(),
+        if self.target.starts_with("thumb") {
+            flags.push("-mthumb".to_string());
+        }
+        let arch = self.target.split('-').next().unwrap_or_default();
+        let mips = arch.starts_with("mips");
+        match &self.cpu {
+            // GCC for MIPS selects the CPU with `-march`
+            Some(cpu) if mips => flags.push(format!("-march={}", cpu)),
+            Some(cpu) => flags.push(format!("-mcpu={}", cpu)),
+            None if mips && arch.starts_with("mipsisa") => {
+                let isa = arch.trim_start_matches("mipsisa").trim_end_matches("el");
+                flags.push(format!("-march=mips{}", isa));
             }
-        } else if target.contains("illumos") {
-            // The default 'ar' on illumos uses a non-standard flags,
-            // but the OS comes bundled with a GNU-compatible variant.
-            //
-            // Use the GNU-variant to match other Unix systems.
-            "gar".to_string()
-        } else if &self.host != target {
-            match self.prefix_for_target(&target) {
-                Some(p) => {
-                    let target_ar = format!("{}-ar", p);
-                    if Command::new(&target_ar).output().is_ok() {
-                        target_ar
-                    } else {
-                        default_ar
-                    }
-                }
-                None => default_ar,
+            None => {}
+        }
+        if mips {
+            if self.target.ends_with("abi64") {
+                flags.push("-mabi=64".to_string());
+            } else if self.target.ends_with("abin32") {
+                flags.push("-mabi=n32".to_string());
             }
-        } else {
-            default_ar
-        };
-        program.into()
+        }
+        if let Some(fpu) = &self.fpu {
+            flags.push(format!("-mfpu={}", fpu));
+        }
+        match self.float_abi.as_deref() {
+            Some(float_abi @ ("soft" | "hard")) if mips => {
+                flags.push(format!("-m{}-float", float_abi))
+            }
+            Some(float_abi) => flags.push(format!("-mfloat-abi={}", float_abi)),
+            None => {}
+        }
+        flags
     }
 
-    fn getenv(&self, v: &str) -> Option<String> {
-        std::env::var(v).ok()
+    /// Whether a 32-bit x86 Linux target is built by the x86_64 GCC with multilib support
+    fn is_x86_multilib(&self) -> bool {
+        let arch = self.target.split('-').next().unwrap_or_default();
+        let cc_name = self.cc.file_name().and_then(|name| name.to_str());
+        matches!(arch, "i586" | "i686")
+            && self.target.contains("-linux-gnu")
+            && cc_name.is_some_and(|name| name.starts_with("x86_64-linux-gnu-"))
     }
 
-    fn get_var(&self, var_base: &str) -> Option<String> {
-        let target = &self.target;
-        let host = &self.host;
-        let kind = if host == target { "HOST" } else { "TARGET" };
-        let target_u = target.replace("-", "_");
-        let res = self
-            .getenv(&format!("{}_{}", var_base, target))
-            .or_else(|| self.getenv(&format!("{}_{}", var_base, target_u)))
-            .or_else(|| self.getenv(&format!("{}_{}", kind, var_base)))
-            .or_else(|| self.getenv(var_base));
-        // FIXME: use Result
-        res
+    /// Target flags the cc crate invokes the C compiler with
+    fn cc_tool_flags(&self) -> Vec<String> {
+        let flags = [&self.arch_flags()[..], &self.cc_args, &self.c_flags].concat();
+        cc_tool_flags(self.cc_tool.as_ref(), &self.cc, &flags)
     }
 
-    fn prefix_for_target(&self, target: &str) -> Option<String> {
-        // CROSS_COMPILE is of the form: "arm-linux-gnueabi-"
-        let cc_env = self.getenv("CROSS_COMPILE");
-        let cross_compile = cc_env.as_ref().map(|s| s.trim_end_matches('-').to_owned());
-        cross_compile.or(match &target[..] {
-            "aarch64-pc-windows-gnu" => Some("aarch64-w64-mingw32"),
-            "aarch64-uwp-windows-gnu" => Some("aarch64-w64-mingw32"),
-            "aarch64-unknown-linux-gnu" => Some("aarch64-linux-gnu"),
-            "aarch64-unknown-linux-musl" => Some("aarch64-linux-musl"),
-            "aarch64-unknown-netbsd" => Some("aarch64--netbsd"),
-            "arm-unknown-linux-gnueabi" => Some("arm-linux-gnueabi"),
-            "armv4t-unknown-linux-gnueabi" => Some("arm-linux-gnueabi"),
-            "armv5te-unknown-linux-gnueabi" => Some("arm-linux-gnueabi"),
-            "armv5te-unknown-linux-musleabi" => Some("arm-linux-gnueabi"),
-            "arm-frc-linux-gnueabi" => Some("arm-frc-linux-gnueabi"),
-            "arm-unknown-linux-gnueabihf" => Some("arm-linux-gnueabihf"),
-            "arm-unknown-linux-musleabi" => Some("arm-linux-musleabi"),
-            "arm-unknown-linux-musleabihf" => Some("arm-linux-musleabihf"),
-            "arm-unknown-netbsd-eabi" => Some("arm--netbsdelf-eabi"),
-            "armv6-unknown-netbsd-eabihf" => Some("armv6--netbsdelf-eabihf"),
-            "armv7-unknown-linux-gnueabi" => Some("arm-linux-gnueabi"),
-            "armv7-unknown-linux-gnueabihf" => Some("arm-linux-gnueabihf"),
-            "armv7-unknown-linux-musleabihf" => Some("arm-linux-musleabihf"),
-            "armv7neon-unknown-linux-gnueabihf" => Some("arm-linux-gnueabihf"),
-            "armv7neon-unknown-linux-musleabihf" => Some("arm-linux-musleabihf"),
-            "thumbv7-unknown-linux-gnueabihf" => Some("arm-linux-gnueabihf"),
-            "thumbv7-unknown-linux-musleabihf" => Some("arm-linux-musleabihf"),
-            "thumbv7neon-unknown-linux-gnueabihf" => Some("arm-linux-gnueabihf"),
-            "thumbv7neon-unknown-linux-musleabihf" => Some("arm-linux-musleabihf"),
-            "armv7-unknown-netbsd-eabihf" => Some("armv7--netbsdelf-eabihf"),
-            "hexagon-unknown-linux-musl" => Some("hexagon-linux-musl"),
-            "i586-unknown-linux-musl" => Some("musl"),
-            "i686-pc-windows-gnu" => Some("i686-w64-mingw32"),
-            "i686-uwp-windows-gnu" => Some("i686-w64-mingw32"),
-            "i686-unknown-linux-gnu" => self.find_working_gnu_prefix(&[
-                "i686-linux-gnu",
-                "x86_64-linux-gnu", // transparently support gcc-multilib
-            ]), // explicit None if not found, so caller knows to fall back
-            "i686-unknown-linux-musl" => Some("musl"),
-            "i686-unknown-netbsd" => Some("i486--netbsdelf"),
-            "mips-unknown-linux-gnu" => Some("mips-linux-gnu"),
-            "mips-unknown-linux-musl" => Some("mips-linux-musl"),
-            "mipsel-unknown-linux-gnu" => Some("mipsel-linux-gnu"),
-            "mipsel-unknown-linux-musl" => Some("mipsel-linux-musl"),
-            "mips64-unknown-linux-gnuabi64" => Some("mips64-linux-gnuabi64"),
-            "mips64el-unknown-linux-gnuabi64" => Some("mips64el-linux-gnuabi64"),
-            "mipsisa32r6-unknown-linux-gnu" => Some("mipsisa32r6-linux-gnu"),
-            "mipsisa32r6el-unknown-linux-gnu" => Some("mipsisa32r6el-linux-gnu"),
-            "mipsisa64r6-unknown-linux-gnuabi64" => Some("mipsisa64r6-linux-gnuabi64"),
-            "mipsisa64r6el-unknown-linux-gnuabi64" => Some("mipsisa64r6el-linux-gnuabi64"),
-            "powerpc-unknown-linux-gnu" => Some("powerpc-linux-gnu"),
-            "powerpc-unknown-linux-gnuspe" => Some("powerpc-linux-gnuspe"),
-            "powerpc-unknown-netbsd" => Some("powerpc--netbsd"),
-            "powerpc64-unknown-linux-gnu" => Some("powerpc-linux-gnu"),
-            "powerpc64le-unknown-linux-gnu" => Some("powerpc64le-linux-gnu"),
-            "riscv32i-unknown-none-elf" => self.find_working_gnu_prefix(&[
-                "riscv32-unknown-elf",
-                "riscv64-unknown-elf",
-                "riscv-none-embed",
-            ]),
-            "riscv32imac-unknown-none-elf" => self.find_working_gnu_prefix(&[
-                "riscv32-unknown-elf",
-                "riscv64-unknown-elf",
-                "riscv-none-embed",
-            ]),
-            "riscv32imc-unknown-none-elf" => self.find_working_gnu_prefix(&[
-                "riscv32-unknown-elf",
-                "riscv64-unknown-elf",
-                "riscv-none-embed",
-            ]),
-            "riscv64gc-unknown-none-elf" => self.find_working_gnu_prefix(&[
-                "riscv64-unknown-elf",
-                "riscv32-unknown-elf",
-                "riscv-none-embed",
-            ]),
-            "riscv64imac-unknown-none-elf" => self.find_working_gnu_prefix(&[
-                "riscv64-unknown-elf",
-                "riscv32-unknown-elf",
-                "riscv-none-embed",
-            ]),
-            "riscv64gc-unknown-linux-gnu" => Some("riscv64-linux-gnu"),
-            "riscv32gc-unknown-linux-gnu" => Some("riscv32-linux-gnu"),
-            "riscv64gc-unknown-linux-musl" => Some("riscv64-linux-musl"),
-            "riscv32gc-unknown-linux-musl" => Some("riscv32-linux-musl"),
-            "s390x-unknown-linux-gnu" => Some("s390x-linux-gnu"),
-            "sparc-unknown-linux-gnu" => Some("sparc-linux-gnu"),
-            "sparc64-unknown-linux-gnu" => Some("sparc64-linux-gnu"),
-            "sparc64-unknown-netbsd" => Some("sparc64--netbsd"),
-            "sparcv9-sun-solaris" => Some("sparcv9-sun-solaris"),
-            "armv7a-none-eabi" => Some("arm-none-eabi"),
-            "armv7a-none-eabihf" => Some("arm-none-eabi"),
-            "armebv7r-none-eabi" => Some("arm-none-eabi"),
-            "armebv7r-none-eabihf" => Some("arm-none-eabi"),
-            "armv7r-none-eabi" => Some("arm-none-eabi"),
-            "armv7r-none-eabihf" => Some("arm-none-eabi"),
-            "thumbv6m-none-eabi" => Some("arm-none-eabi"),
-            "thumbv7em-none-eabi" => Some("arm-none-eabi"),
-            "thumbv7em-none-eabihf" => Some("arm-none-eabi"),
-            "thumbv7m-none-eabi" => Some("arm-none-eabi"),
-            "thumbv8m.base-none-eabi" => Some("arm-none-eabi"),
-            "thumbv8m.main-none-eabi" => Some("arm-none-eabi"),
-            "thumbv8m.main-none-eabihf" => Some("arm-none-eabi"),
-            "x86_64-pc-windows-gnu" => Some("x86_64-w64-mingw32"),
-            "x86_64-uwp-windows-gnu" => Some("x86_64-w64-mingw32"),
-            "x86_64-rumprun-netbsd" => Some("x86_64-rumprun-netbsd"),
-            "x86_64-unknown-linux-gnu" => self.find_working_gnu_prefix(&[
-                "x86_64-linux-gnu", // rustfmt wrap
-            ]), // explicit None if not found, so caller knows to fall back
-            "x86_64-unknown-linux-musl" => Some("musl"),
-            "x86_64-unknown-netbsd" => Some("x86_64--netbsd"),
-            _ => None,
-        }
-        .map(|x| x.to_owned()))
-    }
-
-    /// Some platforms have multiple, compatible, canonical prefixes. Look through
-    /// each possible prefix for a compiler that exists and return it. The prefixes
-    /// should be ordered from most-likely to least-likely.
-    fn find_working_gnu_prefix(&self, prefixes: &[&'static str]) -> Option<&'static str> {
-        // let suffix = if self.cpp { "-g++" } else { "-gcc" };
-        let suffix = "-gcc";
-        let extension = std::env::consts::EXE_SUFFIX;
-
-        // Loop through PATH entries searching for each toolchain. This ensures that we
-        // are more likely to discover the toolchain early on, because chances are good
-        // that the desired toolchain is in one of the higher-priority paths.
-        env::var_os("PATH")
-            .as_ref()
-            .and_then(|path_entries| {
-                env::split_paths(path_entries).find_map(|path_entry| {
-                    for prefix in prefixes {
-                        let target_compiler = format!("{}{}{}", prefix, suffix, extension);
-                        if path_entry.join(&target_compiler).exists() {
-                            return Some(prefix);
-                        }
-                    }
-                    None
-                })
-            })
-            .map(|prefix| *prefix)
-            .or_else(||
-            // If no toolchain was found, provide the first toolchain that was passed in.
-            // This toolchain has been shown not to exist, however it will appear in the
-            // error that is shown to the user which should make it easier to search for
-            // where it should be obtained.
-            prefixes.first().map(|prefix| *prefix))
+    /// Target flags the cc crate invokes the C++ compiler with
+    fn cxx_tool_flags(&self) -> Vec<String> {
+        let flags = [&self.arch_flags()[..], &self.cxx_args, &self.cxx_flags].concat();
+        cc_tool_flags(self.cxx_tool.as_ref(), &self.cxx, &flags)
     }
-}
 
-#[cfg(test)]
-mod test {
-    use super::CMakeToolchain;
+    /// Set CMake system version
+    ///
+    /// Defaults to the API level for Android targets.
+    pub fn system_version(&mut self, system_version: String) -> &mut Self {
+        self.system_version = Some(system_version);
+        self
+    }
 
-    #[test]
-    fn test_cmake_toolchain_for_host() {
-        let meta = rustc_version::version_meta().unwrap();
-        let host = meta.host;
-        let toolchain = CMakeToolchain::new(&host);
-        println!("{:#?}", toolchain);
+    /// Get CMake system version
+    pub fn get_system_version(&self) -> Option<&str> {
+        self.system_version.as_deref()
+    }
+
+    /// Set CMake sysroot
+    pub fn sysroot(&mut self, sysroot: PathBuf) -> &mut Self {
+        self.sysroot = Some(sysroot);
+        self
+    }
+
+    /// Get CMake sysroot
+    pub fn get_sysroot(&self) -> Option<&Path> {
+        self.sysroot.as_deref()
+    }
+
+    /// Detect the sysroot of the C compiler and use it as the CMake sysroot
+    ///
+    /// GCC is asked with `-print-sysroot`. Clang has no such option, so the GNU style
+    /// sysroots `<toolchain root>/<prefix>` of llvm-mingw and `/usr/<prefix>` of Debian are
+    /// tried. The sysroot is left unchanged when none is found.
+    pub fn detect_sysroot(&mut self) -> &mut Self {
+        if let Some(sysroot) = self.find_compiler_sysroot() {
+            self.sysroot = Some(sysroot);
+        }
+        self
+    }
+
+    /// Find the sysroot of the C compiler, see [`CMakeToolchain::detect_sysroot`]
+    fn find_compiler_sysroot(&self) -> Option<PathBuf> {
+        if is_zig(&self.cc) {
+            return None;
+        }
+        let cc = find_program(&self.cc, &self.search_paths())?;
+        let sysroot = match self.compiler_family {
+            CompilerFamily::Gnu => {
+                let output = Command::new(&cc)
+                    .args(&self.cc_args)
+                    .arg("-print-sysroot")
+                    .stderr(Stdio::null())
+                    .output()
+                    .ok()?;
+                if !output.status.success() {
+                    return None;
+                }
+                PathBuf::from(String::from_utf8(output.stdout).ok()?.trim())
+            }
+            CompilerFamily::Clang => {
+                let prefix = self.prefix()?;
+                let root = cc.parent()?.parent()?;
+                [root.join(&prefix), Path::new("/usr").join(&prefix)]
+                    .into_iter()
+                    .find(|dir| dir.join("include").is_dir())?
+            }
+            CompilerFamily::Msvc => return None,
+        };
+        (!sysroot.as_os_str().is_empty() && sysroot.is_dir()).then_some(sysroot)
+    }
+
+    /// Also pass the sysroot as a `--sysroot=<sysroot>` flag, defaults to `false`
+    ///
+    /// `CMAKE_SYSROOT` doesn't reach the preprocessor before CMake 3.9, which breaks some
+    /// clang cross compiles.
+    pub fn sysroot_as_flag(&mut self, sysroot_as_flag: bool) -> &mut Self {
+        self.sysroot_as_flag = sysroot_as_flag;
+        self
+    }
+
+    /// Get whether the sysroot is also passed as a `--sysroot` flag
+    pub fn get_sysroot_as_flag(&self) -> bool {
+        self.sysroot_as_flag
+    }
+
+    /// Link executables statically for musl targets, defaults to `true`
+    ///
+    /// Like Rust's musl targets, this passes `-static` to the linker and adds the sysroot of
+    /// a musl cross toolchain to `CMAKE_FIND_ROOT_PATH`, so host glibc libraries aren't linked.
+    pub fn musl_static(&mut self, enable: bool) -> &mut Self {
+        self.musl_static = enable;
+        self
+    }
+
+    /// Get whether executables are linked statically for musl targets
+    pub fn get_musl_static(&self) -> bool {
+        self.musl_static
+    }
+
+    /// Set the cross compile prefix of the binutils, like `aarch64-linux-gnu`
+    ///
+    /// This takes precedence over the `CROSS_COMPILE` environment variable and the built-in
+    /// prefixes, which is useful for targets the crate doesn't know about yet. Tools that
+    /// weren't set explicitly are resolved again.
+    pub fn prefix_override(&mut self, prefix: String) -> &mut Self {
+        let prefix = prefix.trim_end_matches('-').to_string();
+        self.update_tools(|toolchain| toolchain.prefix_override = Some(prefix))
+    }
+
+    /// Get the cross compile prefix set with [`CMakeToolchain::prefix_override`]
+    pub fn get_prefix_override(&self) -> Option<&str> {
+        self.prefix_override.as_deref()
+    }
+
+    /// Set the executable suffixes tried when looking for the cross compiler on `PATH`
+    ///
+    /// Defaults to the suffix of the host, no suffix and `.exe`, so a Linux host finds a
+    /// `.exe` compiler on a WSL mounted path and a Windows host a compiler without suffix.
+    /// Tools that weren't set explicitly are resolved again.
+    pub fn path_exe_suffixes(&mut self, suffixes: Vec<String>) -> &mut Self {
+        self.update_tools(|toolchain| toolchain.path_exe_suffixes = Some(suffixes))
+    }
+
+    /// Get the executable suffixes tried when looking for the cross compiler on `PATH`
+    pub fn get_path_exe_suffixes(&self) -> Vec<&str> {
+        match &self.path_exe_suffixes {
+            Some(suffixes) => suffixes.iter().map(String::as_str).collect(),
+            None => default_exe_suffixes(),
+        }
+    }
+
+    /// Set a toolchain installation like `/opt/gcc-arm-none-eabi`, its `bin` directory is
+    /// searched for the compilers and binutils before `PATH`
+    ///
+    /// The process `PATH` isn't changed, tools found in the toolchain are written with their
+    /// full path. Tools that weren't set explicitly are resolved again.
+    pub fn toolchain_root(&mut self, root: PathBuf) -> &mut Self {
+        self.update_tools(|toolchain| toolchain.toolchain_root = Some(root))
+    }
+
+    /// Get the toolchain installation set with [`CMakeToolchain::toolchain_root`]
+    pub fn get_toolchain_root(&self) -> Option<&Path> {
+        self.toolchain_root.as_deref()
+    }
+
+    /// Directories searched for tools, the toolchain `bin` directory followed by `PATH`
+    fn search_paths(&self) -> Vec<PathBuf> {
+        self.toolchain_root
+            .iter()
+            .map(|root| root.join("bin"))
+            .chain(path_entries())
+            .collect()
+    }
+
+    /// Replace a bare program name with its path when it's installed in the toolchain root
+    fn in_toolchain_root(&self, program: PathBuf) -> PathBuf {
+        let root = match &self.toolchain_root {
+            Some(root) if program.components().count() == 1 => root,
+            _ => return program,
+        };
+        find_program(&program, &[root.join("bin")]).unwrap_or(program)
+    }
+
+    /// Use `zig cc`/`zig c++` as the compilers and `zig ar`/`zig ranlib` as the archiver
+    ///
+    /// This is enabled automatically when `CC` is set to `zig cc`. The target is passed to
+    /// zig with `-target`, see [`zig_target`]. Tools that weren't set explicitly are resolved
+    /// again.
+    pub fn use_zig(&mut self, enable: bool) -> &mut Self {
+        self.update_tools(|toolchain| toolchain.use_zig = enable)
+    }
+
+    /// Get whether zig is used as the compiler
+    pub fn get_use_zig(&self) -> bool {
+        is_zig(&self.cc)
+    }
+
+    /// Use the LLVM binutils `llvm-ar`, `llvm-ranlib`, `llvm-strip` and `llvm-nm`
+    ///
+    /// Unlike the GNU binutils they understand LLVM bitcode, which LTO builds with clang
+    /// need. By default they are used for clang when found on `PATH`. A versioned tool like
+    /// `llvm-ar-17` matching a `clang-17` compiler is preferred. Tools that weren't set
+    /// explicitly are resolved again.
+    pub fn use_llvm_binutils(&mut self, enable: bool) -> &mut Self {
+        self.update_tools(|toolchain| toolchain.llvm_binutils = Some(enable))
+    }
+
+    /// Get whether the LLVM binutils are used, `None` if decided by the compiler family
+    pub fn get_use_llvm_binutils(&self) -> Option<bool> {
+        self.llvm_binutils
+    }
+
+    /// Set the directory searched for the binutils before `PATH`
+    ///
+    /// Defaults to the directory of the C compiler, so the tools of a self-contained
+    /// toolchain like `/opt/tc/bin/aarch64-linux-gnu-ar` are found next to
+    /// `/opt/tc/bin/aarch64-linux-gnu-gcc`. Tools that weren't set explicitly are resolved
+    /// again.
+    pub fn tool_search_dir(&mut self, dir: PathBuf) -> &mut Self {
+        self.update_tools(|toolchain| toolchain.tool_search_dir = Some(dir))
+    }
+
+    /// Get the directory searched for the binutils
+    pub fn get_tool_search_dir(&self) -> Option<&Path> {
+        self.tool_search_dir
+            .as_deref()
+            .or_else(|| self.cc.parent().filter(|dir| !dir.as_os_str().is_empty()))
+    }
+
+    /// Set C compiler path
+    ///
+    /// Unlike a `CC` environment variable the path is never split into a compiler and its
+    /// arguments, see [`CMakeToolchain::cc_command`] for that.
+    pub fn cc(&mut self, cc: PathBuf) -> &mut Self {
+        self.cc = cc;
+        self.cc_args.clear();
+        self.set_source("cc", ToolSource::Explicit);
+        self.derive_compiler_family(None);
+        self
+    }
+
+    /// Set the C compiler from a command like `ccache clang -m32`
+    ///
+    /// The command is split like a `CC` environment variable, a leading launcher like `ccache`
+    /// becomes the compiler launcher and the words after the compiler its arguments.
+    pub fn cc_command(&mut self, command: &str) -> &mut Self {
+        let (launcher, cc, args) = split_compiler(command, "cc");
+        self.cc = cc;
+        self.cc_args = args;
+        if launcher.is_some() {
+            self.compiler_launcher = launcher;
+        }
+        self.set_source("cc", ToolSource::Explicit);
+        self.derive_compiler_family(None);
+        self
+    }
+
+    /// Get C compiler path
+    pub fn get_cc(&self) -> &Path {
+        &self.cc
+    }
+
+    /// Set C++ compiler path
+    ///
+    /// Unlike a `CXX` environment variable the path is never split into a compiler and its
+    /// arguments, see [`CMakeToolchain::cxx_command`] for that.
+    pub fn cxx(&mut self, cxx: PathBuf) -> &mut Self {
+        self.cxx = cxx;
+        self.cxx_args.clear();
+        self.set_source("cxx", ToolSource::Explicit);
+        self
+    }
+
+    /// Set the C++ compiler from a command like `ccache clang++ -stdlib=libc++`
+    ///
+    /// The command is split like [`CMakeToolchain::cc_command`].
+    pub fn cxx_command(&mut self, command: &str) -> &mut Self {
+        let (launcher, cxx, args) = split_compiler(command, "c++");
+        self.cxx = cxx;
+        self.cxx_args = args;
+        if launcher.is_some() {
+            self.compiler_launcher = launcher;
+        }
+        self.set_source("cxx", ToolSource::Explicit);
+        self
+    }
+
+    /// Get C++ compiler path
+    pub fn get_cxx(&self) -> &Path {
+        &self.cxx
+    }
+
+    /// Set Fortran compiler path
+    ///
+    /// Defaults to `FC`, `gfortran` next to the C compiler or `<prefix>-gfortran` on `PATH`
+    /// when cross compiling. No programs are run to find it.
+    pub fn fortran(&mut self, fortran: PathBuf) -> &mut Self {
+        self.fortran = Some(fortran);
+        self.set_source("fortran", ToolSource::Explicit);
+        self
+    }
+
+    /// Get Fortran compiler path
+    pub fn get_fortran(&self) -> Option<&Path> {
+        self.fortran.as_deref()
+    }
+
+    /// Set compiler launcher, like `ccache` or `sccache`
+    ///
+    /// Defaults to the `CMAKE_COMPILER_LAUNCHER` or `RUSTC_WRAPPER` environment variable.
+    pub fn compiler_launcher(&mut self, launcher: PathBuf) -> &mut Self {
+        self.compiler_launcher = Some(launcher);
+        self
+    }
+
+    /// Get compiler launcher
+    pub fn get_compiler_launcher(&self) -> Option<&Path> {
+        self.compiler_launcher.as_deref()
+    }
+
+    /// Set assembler path
+    pub fn asm(&mut self, asm: PathBuf) -> &mut Self {
+        self.asm = Some(asm);
+        self.set_source("asm", ToolSource::Explicit);
+        self
+    }
+
+    /// Get assembler path
+    pub fn get_asm(&self) -> &Path {
+        self.asm.as_deref().unwrap_or(&self.cc)
+    }
+
+    /// Add a C compiler flag
+    pub fn add_c_flag(&mut self, flag: &str) -> &mut Self {
+        self.c_flags.push(flag.to_string());
+        self
+    }
+
+    /// Get C compiler flags
+    pub fn get_c_flags(&self) -> &[String] {
+        &self.c_flags
+    }
+
+    /// Add a C++ compiler flag
+    pub fn add_cxx_flag(&mut self, flag: &str) -> &mut Self {
+        self.cxx_flags.push(flag.to_string());
+        self
+    }
+
+    /// Get C++ compiler flags
+    pub fn get_cxx_flags(&self) -> &[String] {
+        &self.cxx_flags
+    }
+
+    /// Set C/C++ compiler family
+    ///
+    /// Defaults to the family of the C compiler, and follows it when the C compiler is
+    /// changed unless set with this. The compiler target is derived again, the
+    /// [`CMakeToolchain::llvm_triple`] for clang and none otherwise.
+    pub fn compiler_family(&mut self, compiler_family: CompilerFamily) -> &mut Self {
+        self.compiler_family = compiler_family;
+        self.compiler_family_explicit = true;
+        self.compiler_target = self.default_compiler_target();
+        self
+    }
+
+    /// Derive the compiler family from the C compiler unless it was set explicitly
+    ///
+    /// `detected` is the family the cc crate detected, it's guessed from the compiler name
+    /// otherwise.
+    fn derive_compiler_family(&mut self, detected: Option<CompilerFamily>) {
+        if self.compiler_family_explicit {
+            return;
+        }
+        self.compiler_family = if is_zig(&self.cc) {
+            CompilerFamily::Clang
+        } else {
+            detected.unwrap_or_else(|| CompilerFamily::from_path(&self.cc))
+        };
+        self.compiler_target = self.default_compiler_target();
+    }
+
+    /// The LLVM target triple of clang, zig takes its own target names through `-target`
+    fn default_compiler_target(&self) -> Option<String> {
+        if self.compiler_family == CompilerFamily::Clang && !is_zig(&self.cc) {
+            Some(self.llvm_triple())
+        } else {
+            None
+        }
+    }
+
+    /// Get C/C++ compiler family
+    pub fn get_compiler_family(&self) -> CompilerFamily {
+        self.compiler_family
+    }
+
+    /// Get the LLVM target triple of the target, as passed to clang's `--target`
+    ///
+    /// This differs from the Rust target for Apple, Android, RISC-V and some ARM targets,
+    /// like `arm64-apple-macosx` for `aarch64-apple-darwin`.
+    pub fn llvm_triple(&self) -> String {
+        let target = &self.target;
+        // Android clang targets an API level, like `aarch64-linux-android21`
+        if target.contains("android") {
+            return format!(
+                "{}{}",
+                android_ndk_prefixes(target).0,
+                self.android_api_level
+            );
+        }
+        // The OpenHarmony clang uses the GNU style triples, like `aarch64-linux-ohos`
+        if target.ends_with("-ohos") {
+            if let Some(prefix) = gnu_prefixes(target).first() {
+                return prefix.to_string();
+            }
+        }
+        let mut parts: Vec<&str> = target.split('-').collect();
+        let arch = parts[0];
+        parts[0] = if target.contains("-apple-") {
+            apple_arch(target)
+        } else if arch.starts_with("riscv64") {
+            "riscv64"
+        } else if arch.starts_with("riscv32") {
+            "riscv32"
+        } else if arch == "thumbv7neon" {
+            "armv7a"
+        } else {
+            arch
+        };
+        if let Some(env) = parts.last_mut().filter(|env| **env == "gnullvm") {
+            *env = "gnu";
+        }
+        if let Some(sdk) = apple_sdk_name(target) {
+            if parts.last() == Some(&"sim") {
+                parts.pop();
+            }
+            if let Some(os) = parts.iter_mut().find(|os| **os == "darwin") {
+                *os = "macosx";
+            }
+            if sdk.ends_with("simulator") {
+                parts.push("simulator");
+            }
+        }
+        parts.join("-")
+    }
+
+    /// Set the LLVM target triple passed to clang via `CMAKE_<LANG>_COMPILER_TARGET`
+    ///
+    /// Defaults to [`CMakeToolchain::llvm_triple`] when the C compiler is clang.
+    pub fn compiler_target(&mut self, compiler_target: String) -> &mut Self {
+        self.compiler_target = Some(compiler_target);
+        self
+    }
+
+    /// Get the LLVM target triple passed to clang
+    pub fn get_compiler_target(&self) -> Option<&str> {
+        self.compiler_target.as_deref()
+    }
+
+    /// Add a linker flag for executables
+    pub fn add_exe_linker_flag(&mut self, flag: &str) -> &mut Self {
+        self.exe_linker_flags.push(flag.to_string());
+        self
+    }
+
+    /// Get linker flags for executables
+    pub fn get_exe_linker_flags(&self) -> &[String] {
+        &self.exe_linker_flags
+    }
+
+    /// Add a linker flag for shared libraries
+    pub fn add_shared_linker_flag(&mut self, flag: &str) -> &mut Self {
+        self.shared_linker_flags.push(flag.to_string());
+        self
+    }
+
+    /// Get linker flags for shared libraries
+    pub fn get_shared_linker_flags(&self) -> &[String] {
+        &self.shared_linker_flags
+    }
+
+    /// Add a linker flag for modules
+    pub fn add_module_linker_flag(&mut self, flag: &str) -> &mut Self {
+        self.module_linker_flags.push(flag.to_string());
+        self
+    }
+
+    /// Get linker flags for modules
+    pub fn get_module_linker_flags(&self) -> &[String] {
+        &self.module_linker_flags
+    }
+
+    /// Set archiver path
+    pub fn ar(&mut self, ar: PathBuf) -> &mut Self {
+        self.ar = ar;
+        self.set_source("ar", ToolSource::Explicit);
+        self
+    }
+
+    /// Get archiver path
+    pub fn get_ar(&self) -> &Path {
+        &self.ar
+    }
+
+    /// Set ranlib path
+    pub fn ranlib(&mut self, ranlib: PathBuf) -> &mut Self {
+        self.ranlib = Some(ranlib);
+        self.set_source("ranlib", ToolSource::Explicit);
+        self
+    }
+
+    /// Get ranlib path, `None` if the target doesn't use ranlib
+    pub fn get_ranlib(&self) -> Option<&Path> {
+        self.ranlib.as_deref()
+    }
+
+    /// Set the command running target programs, `CMAKE_CROSSCOMPILING_EMULATOR`
+    ///
+    /// Used by `try_run` and `ctest` when cross compiling, like `["qemu-aarch64", "-L",
+    /// "/usr/aarch64-linux-gnu"]`.
+    pub fn emulator(&mut self, command: Vec<String>) -> &mut Self {
+        self.emulator = command;
+        self
+    }
+
+    /// Get the command running target programs, empty if there is none
+    ///
+    /// This includes the qemu command derived with [`CMakeToolchain::auto_emulator`].
+    pub fn get_emulator(&self) -> Vec<String> {
+        if !self.emulator.is_empty() {
+            return self.emulator.clone();
+        }
+        let sysroot = match &self.sysroot {
+            Some(sysroot) if self.auto_emulator && self.is_cross_compiling() => sysroot,
+            _ => return Vec::new(),
+        };
+        match qemu_arch(&self.target) {
+            Some(arch) => vec![
+                format!("qemu-{}", arch),
+                "-L".to_string(),
+                sysroot.to_string_lossy().into_owned(),
+            ],
+            None => Vec::new(),
+        }
+    }
+
+    /// Run target programs of Linux targets with qemu user mode emulation, defaults to `false`
+    ///
+    /// The emulator is `qemu-<arch> -L <sysroot>`, so it needs a sysroot. An emulator set with
+    /// [`CMakeToolchain::emulator`] takes precedence.
+    pub fn auto_emulator(&mut self, enable: bool) -> &mut Self {
+        self.auto_emulator = enable;
+        self
+    }
+
+    /// Get whether the qemu emulator is derived from the target
+    pub fn get_auto_emulator(&self) -> bool {
+        self.auto_emulator
+    }
+
+    /// Skip embedding rpaths, `CMAKE_SKIP_BUILD_RPATH` and `CMAKE_SKIP_RPATH`
+    ///
+    /// Defaults to `true` when cross compiling, where the rpaths of the build tree point to
+    /// host directories. With an [`CMakeToolchain::install_rpath`] only the build tree rpath
+    /// is skipped.
+    pub fn skip_rpath(&mut self, skip: bool) -> &mut Self {
+        self.skip_rpath = Some(skip);
+        self
+    }
+
+    /// Get whether rpaths are skipped
+    pub fn get_skip_rpath(&self) -> bool {
+        self.skip_rpath.unwrap_or_else(|| self.is_cross_compiling())
+    }
+
+    /// Set the rpath of installed binaries, `CMAKE_INSTALL_RPATH`
+    ///
+    /// Multiple directories are separated with `;`, like `$ORIGIN/../lib;/opt/app/lib`.
+    pub fn install_rpath(&mut self, rpath: String) -> &mut Self {
+        self.install_rpath = Some(rpath);
+        self
+    }
+
+    /// Get the rpath of installed binaries
+    pub fn get_install_rpath(&self) -> Option<&str> {
+        self.install_rpath.as_deref()
+    }
+
+    /// Emit the archiver and ranlib, defaults to `true`
+    ///
+    /// When disabled CMake finds `CMAKE_AR` and `CMAKE_RANLIB` itself, for example to use
+    /// the `gcc-ar` wrappers it picks for interprocedural optimization.
+    pub fn emit_archiver(&mut self, enable: bool) -> &mut Self {
+        self.emit_archiver = enable;
+        self
+    }
+
+    /// Get whether the archiver and ranlib are emitted
+    pub fn get_emit_archiver(&self) -> bool {
+        self.emit_archiver
+    }
+
+    /// Set the rule creating a static library, `CMAKE_<LANG>_ARCHIVE_CREATE`
+    ///
+    /// Like `<CMAKE_AR> qc <TARGET> <LINK_FLAGS> <OBJECTS>`, it's set for C, C++ and assembly.
+    /// There are defaults for `zig ar`, for `llvm-ar` with Apple targets and for `gar`,
+    /// otherwise the one of CMake is used.
+    pub fn archive_create(&mut self, rule: String) -> &mut Self {
+        self.archive_create = Some(rule);
+        self
+    }
+
+    /// Get the rule creating a static library set with [`CMakeToolchain::archive_create`]
+    pub fn get_archive_create(&self) -> Option<&str> {
+        self.archive_create.as_deref()
+    }
+
+    /// Set the rule appending to a static library, `CMAKE_<LANG>_ARCHIVE_APPEND`
+    pub fn archive_append(&mut self, rule: String) -> &mut Self {
+        self.archive_append = Some(rule);
+        self
+    }
+
+    /// Get the rule appending to a static library set with [`CMakeToolchain::archive_append`]
+    pub fn get_archive_append(&self) -> Option<&str> {
+        self.archive_append.as_deref()
+    }
+
+    /// Set the rule finishing a static library, `CMAKE_<LANG>_ARCHIVE_FINISH`
+    ///
+    /// Like `<CMAKE_RANLIB> <TARGET>`, which indexes the archive.
+    pub fn archive_finish(&mut self, rule: String) -> &mut Self {
+        self.archive_finish = Some(rule);
+        self
+    }
+
+    /// Get the rule finishing a static library set with [`CMakeToolchain::archive_finish`]
+    pub fn get_archive_finish(&self) -> Option<&str> {
+        self.archive_finish.as_deref()
+    }
+
+    /// The archive create, append and finish rules, `None` to use the ones of CMake
+    fn archive_rules(&self) -> [Option<String>; 3] {
+        let ar_name = self
+            .ar
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default();
+        // The defaults only fit the archiver of the toolchain, not one CMake finds itself
+        let defaults: [Option<&str>; 3] = if !self.emit_archiver {
+            [None, None, None]
+        } else if is_zig(&self.ar) {
+            let finish = match self.ranlib.as_deref() {
+                Some(ranlib) if is_zig(ranlib) => "<CMAKE_RANLIB> ranlib <TARGET>",
+                _ => "<CMAKE_RANLIB> <TARGET>",
+            };
+            [
+                Some("<CMAKE_AR> ar qc <TARGET> <LINK_FLAGS> <OBJECTS>"),
+                Some("<CMAKE_AR> ar q <TARGET> <LINK_FLAGS> <OBJECTS>"),
+                Some(finish),
+            ]
+        } else if ar_name.starts_with("llvm-ar") && self.target.contains("-apple-") {
+            // llvm-ar writes GNU archives on other hosts, which the Apple linker rejects
+            [
+                Some("<CMAKE_AR> --format=darwin qc <TARGET> <LINK_FLAGS> <OBJECTS>"),
+                Some("<CMAKE_AR> --format=darwin q <TARGET> <LINK_FLAGS> <OBJECTS>"),
+                None,
+            ]
+        } else if ar_name == "gar" {
+            // Let `gar` write the symbol index itself, the illumos ranlib may not understand it
+            [
+                Some("<CMAKE_AR> qcs <TARGET> <LINK_FLAGS> <OBJECTS>"),
+                Some("<CMAKE_AR> qs <TARGET> <LINK_FLAGS> <OBJECTS>"),
+                None,
+            ]
+        } else {
+            [None, None, None]
+        };
+        let [create, append, finish] = defaults.map(|rule| rule.map(String::from));
+        [
+            self.archive_create.clone().or(create),
+            self.archive_append.clone().or(append),
+            self.archive_finish.clone().or(finish),
+        ]
+    }
+
+    /// Set strip path
+    pub fn strip(&mut self, strip: PathBuf) -> &mut Self {
+        self.strip = Some(strip);
+        self.set_source("strip", ToolSource::Explicit);
+        self
+    }
+
+    /// Get strip path
+    pub fn get_strip(&self) -> Option<&Path> {
+        self.strip.as_deref()
+    }
+
+    /// Set nm path
+    pub fn nm(&mut self, nm: PathBuf) -> &mut Self {
+        self.nm = Some(nm);
+        self.set_source("nm", ToolSource::Explicit);
+        self
+    }
+
+    /// Get nm path
+    pub fn get_nm(&self) -> Option<&Path> {
+        self.nm.as_deref()
+    }
+
+    /// Set objcopy path
+    pub fn objcopy(&mut self, objcopy: PathBuf) -> &mut Self {
+        self.objcopy = Some(objcopy);
+        self.set_source("objcopy", ToolSource::Explicit);
+        self
+    }
+
+    /// Get objcopy path
+    pub fn get_objcopy(&self) -> Option<&Path> {
+        self.objcopy.as_deref()
+    }
+
+    /// Set objdump path
+    pub fn objdump(&mut self, objdump: PathBuf) -> &mut Self {
+        self.objdump = Some(objdump);
+        self.set_source("objdump", ToolSource::Explicit);
+        self
+    }
+
+    /// Get objdump path
+    pub fn get_objdump(&self) -> Option<&Path> {
+        self.objdump.as_deref()
+    }
+
+    /// Set resource compiler path
+    pub fn rc(&mut self, rc: PathBuf) -> &mut Self {
+        self.rc = Some(rc);
+        self.set_source("rc", ToolSource::Explicit);
+        self
+    }
+
+    /// Get resource compiler path
+    pub fn get_rc(&self) -> Option<&Path> {
+        self.rc.as_deref()
+    }
+
+    /// Set linker path, like `link.exe` or `ld.lld`
+    ///
+    /// Defaults to the `[target.<triple>] linker` of the Cargo configuration with the
+    /// `cargo-config` feature, or the `link.exe` of the target architecture for windows-msvc
+    /// targets.
+    pub fn linker(&mut self, linker: PathBuf) -> &mut Self {
+        self.linker = Some(linker);
+        self.set_source("linker", ToolSource::Explicit);
+        self
+    }
+
+    /// Get linker path
+    pub fn get_linker(&self) -> Option<&Path> {
+        self.linker.as_deref()
+    }
+
+    /// Set manifest tool path, `mt.exe` for MSVC toolchains
+    pub fn mt(&mut self, mt: PathBuf) -> &mut Self {
+        self.mt = Some(mt);
+        self.set_source("mt", ToolSource::Explicit);
+        self
+    }
+
+    /// Get manifest tool path
+    pub fn get_mt(&self) -> Option<&Path> {
+        self.mt.as_deref()
+    }
+
+    /// Get the byte order of the target
+    ///
+    /// Targets that can't be parsed are assumed to be little endian.
+    pub fn endianness(&self) -> Endianness {
+        match target_lexicon::Triple::from_str(&self.target).map(|triple| triple.endianness()) {
+            Ok(Ok(target_lexicon::Endianness::Big)) => Endianness::Big,
+            _ => Endianness::Little,
+        }
+    }
+
+    /// Set Apple platforms deployment target, like `10.12` for macOS
+    ///
+    /// Defaults to the `*_DEPLOYMENT_TARGET` environment variable of the target platform,
+    /// e.g. `MACOSX_DEPLOYMENT_TARGET` or `IPHONEOS_DEPLOYMENT_TARGET`.
+    pub fn osx_deployment_target(&mut self, deployment_target: String) -> &mut Self {
+        self.osx_deployment_target = Some(deployment_target);
+        self
+    }
+
+    /// Get Apple platforms deployment target
+    pub fn get_osx_deployment_target(&self) -> Option<&str> {
+        self.osx_deployment_target.as_deref()
+    }
+
+    /// Set Android API level, defaults to 21
+    ///
+    /// This also sets the CMake system version, and tools that weren't set explicitly are
+    /// resolved again since NDK r19+ encodes the API level into the clang wrapper names and
+    /// clang's `--target`.
+    pub fn android_api_level(&mut self, level: u32) -> &mut Self {
+        self.update_tools(|toolchain| {
+            toolchain.android_api_level = level;
+            if toolchain.target.contains("android") {
+                toolchain.system_version = Some(level.to_string());
+            }
+        })
+    }
+
+    /// Get Android API level
+    pub fn get_android_api_level(&self) -> u32 {
+        self.android_api_level
+    }
+
+    /// Set `CMAKE_STAGING_PREFIX`, where `make install` puts files when cross compiling
+    ///
+    /// Use this to keep the sysroot pristine, installs otherwise go into the sysroot.
+    pub fn staging_prefix(&mut self, prefix: PathBuf) -> &mut Self {
+        self.staging_prefix = Some(prefix);
+        self
+    }
+
+    /// Get `CMAKE_STAGING_PREFIX`
+    pub fn get_staging_prefix(&self) -> Option<&Path> {
+        self.staging_prefix.as_deref()
+    }
+
+    /// Set `CMAKE_INSTALL_PREFIX`, the install location on the target system
+    pub fn install_prefix(&mut self, prefix: PathBuf) -> &mut Self {
+        self.install_prefix = Some(prefix);
+        self
+    }
+
+    /// Get `CMAKE_INSTALL_PREFIX`
+    pub fn get_install_prefix(&self) -> Option<&Path> {
+        self.install_prefix.as_deref()
+    }
+
+    /// Add a `CMAKE_FIND_ROOT_PATH` location
+    ///
+    /// The sysroot is always searched first when set.
+    pub fn add_find_root_path(&mut self, path: PathBuf) -> &mut Self {
+        self.find_root_path.push(path);
+        self
+    }
+
+    /// Get the `CMAKE_FIND_ROOT_PATH` locations, including the sysroot
+    pub fn get_find_root_path(&self) -> Vec<&Path> {
+        let mut paths: Vec<&Path> = self.sysroot.as_deref().into_iter().collect();
+        let musl_root = self.musl_root.as_ref().filter(|_| self.musl_static);
+        for path in musl_root.into_iter().chain(&self.find_root_path) {
+            if !paths.contains(&path.as_path()) {
+                paths.push(path);
+            }
+        }
+        paths
+    }
+
+    /// Set `CMAKE_FIND_ROOT_PATH_MODE_PROGRAM`, defaults to [`FindRootPathMode::Never`]
+    pub fn find_root_path_mode_program(&mut self, mode: FindRootPathMode) -> &mut Self {
+        self.find_root_path_mode_program = mode;
+        self
+    }
+
+    /// Get `CMAKE_FIND_ROOT_PATH_MODE_PROGRAM`
+    pub fn get_find_root_path_mode_program(&self) -> FindRootPathMode {
+        self.find_root_path_mode_program
+    }
+
+    /// Set `CMAKE_FIND_ROOT_PATH_MODE_LIBRARY`, defaults to [`FindRootPathMode::Only`]
+    pub fn find_root_path_mode_library(&mut self, mode: FindRootPathMode) -> &mut Self {
+        self.find_root_path_mode_library = mode;
+        self
+    }
+
+    /// Get `CMAKE_FIND_ROOT_PATH_MODE_LIBRARY`
+    pub fn get_find_root_path_mode_library(&self) -> FindRootPathMode {
+        self.find_root_path_mode_library
+    }
+
+    /// Set `CMAKE_FIND_ROOT_PATH_MODE_INCLUDE`, defaults to [`FindRootPathMode::Only`]
+    pub fn find_root_path_mode_include(&mut self, mode: FindRootPathMode) -> &mut Self {
+        self.find_root_path_mode_include = mode;
+        self
+    }
+
+    /// Get `CMAKE_FIND_ROOT_PATH_MODE_INCLUDE`
+    pub fn get_find_root_path_mode_include(&self) -> FindRootPathMode {
+        self.find_root_path_mode_include
+    }
+
+    /// Set `CMAKE_FIND_ROOT_PATH_MODE_PACKAGE`, defaults to [`FindRootPathMode::Only`]
+    pub fn find_root_path_mode_package(&mut self, mode: FindRootPathMode) -> &mut Self {
+        self.find_root_path_mode_package = mode;
+        self
+    }
+
+    /// Get `CMAKE_FIND_ROOT_PATH_MODE_PACKAGE`
+    pub fn get_find_root_path_mode_package(&self) -> FindRootPathMode {
+        self.find_root_path_mode_package
+    }
+
+    /// Set `CMAKE_TRY_COMPILE_TARGET_TYPE`
+    ///
+    /// Defaults to [`TryCompileTargetType::StaticLibrary`] for bare metal targets.
+    pub fn try_compile_target_type(&mut self, target_type: TryCompileTargetType) -> &mut Self {
+        self.try_compile_target_type = Some(target_type);
+        self
+    }
+
+    /// Get `CMAKE_TRY_COMPILE_TARGET_TYPE`
+    pub fn get_try_compile_target_type(&self) -> Option<TryCompileTargetType> {
+        self.try_compile_target_type
+    }
+
+    /// Set `CMAKE_EXECUTABLE_SUFFIX`
+    ///
+    /// Defaults to `.wasm` for wasm targets and `.elf` for bare metal targets.
+    pub fn executable_suffix(&mut self, suffix: String) -> &mut Self {
+        self.executable_suffix = Some(suffix);
+        self
+    }
+
+    /// Get `CMAKE_EXECUTABLE_SUFFIX`
+    pub fn get_executable_suffix(&self) -> Option<&str> {
+        self.executable_suffix.as_deref()
+    }
+
+    /// Set `CMAKE_STATIC_LIBRARY_SUFFIX`
+    pub fn static_library_suffix(&mut self, suffix: String) -> &mut Self {
+        self.static_library_suffix = Some(suffix);
+        self
+    }
+
+    /// Get `CMAKE_STATIC_LIBRARY_SUFFIX`
+    pub fn get_static_library_suffix(&self) -> Option<&str> {
+        self.static_library_suffix.as_deref()
+    }
+
+    /// Set `CMAKE_SHARED_LIBRARY_SUFFIX`
+    pub fn shared_library_suffix(&mut self, suffix: String) -> &mut Self {
+        self.shared_library_suffix = Some(suffix);
+        self
+    }
+
+    /// Get `CMAKE_SHARED_LIBRARY_SUFFIX`
+    pub fn get_shared_library_suffix(&self) -> Option<&str> {
+        self.shared_library_suffix.as_deref()
+    }
+
+    /// Set `CMAKE_C_STANDARD`, one of 90, 99, 11, 17 or 23
+    ///
+    /// Other values are reported by [`CMakeToolchain::validate`].
+    pub fn c_standard(&mut self, standard: u32) -> &mut Self {
+        self.c_standard = Some(standard);
+        self
+    }
+
+    /// Get `CMAKE_C_STANDARD`
+    pub fn get_c_standard(&self) -> Option<u32> {
+        self.c_standard
+    }
+
+    /// Set `CMAKE_C_STANDARD_REQUIRED`, to fail instead of falling back to an older standard
+    pub fn c_standard_required(&mut self, required: bool) -> &mut Self {
+        self.c_standard_required = Some(required);
+        self
+    }
+
+    /// Get `CMAKE_C_STANDARD_REQUIRED`, `None` if not set
+    pub fn get_c_standard_required(&self) -> Option<bool> {
+        self.c_standard_required
+    }
+
+    /// Set `CMAKE_C_EXTENSIONS`, whether to use `-std=gnu11` rather than `-std=c11`
+    pub fn c_extensions(&mut self, enable: bool) -> &mut Self {
+        self.c_extensions = Some(enable);
+        self
+    }
+
+    /// Get `CMAKE_C_EXTENSIONS`, `None` if not set
+    pub fn get_c_extensions(&self) -> Option<bool> {
+        self.c_extensions
+    }
+
+    /// Set `CMAKE_CXX_STANDARD`, one of 98, 11, 14, 17, 20 or 23
+    ///
+    /// Other values are reported by [`CMakeToolchain::validate`].
+    pub fn cxx_standard(&mut self, standard: u32) -> &mut Self {
+        self.cxx_standard = Some(standard);
+        self
+    }
+
+    /// Get `CMAKE_CXX_STANDARD`
+    pub fn get_cxx_standard(&self) -> Option<u32> {
+        self.cxx_standard
+    }
+
+    /// Set `CMAKE_CXX_STANDARD_REQUIRED`, to fail instead of falling back to an older standard
+    pub fn cxx_standard_required(&mut self, required: bool) -> &mut Self {
+        self.cxx_standard_required = Some(required);
+        self
+    }
+
+    /// Get `CMAKE_CXX_STANDARD_REQUIRED`, `None` if not set
+    pub fn get_cxx_standard_required(&self) -> Option<bool> {
+        self.cxx_standard_required
+    }
+
+    /// Set `CMAKE_CXX_EXTENSIONS`, whether to use `-std=gnu++17` rather than `-std=c++17`
+    pub fn cxx_extensions(&mut self, enable: bool) -> &mut Self {
+        self.cxx_extensions = Some(enable);
+        self
+    }
+
+    /// Get `CMAKE_CXX_EXTENSIONS`, `None` if not set
+    pub fn get_cxx_extensions(&self) -> Option<bool> {
+        self.cxx_extensions
+    }
+
+    /// Set `CMAKE_POSITION_INDEPENDENT_CODE`
+    pub fn position_independent_code(&mut self, enable: bool) -> &mut Self {
+        self.position_independent_code = Some(enable);
+        self
+    }
+
+    /// Get `CMAKE_POSITION_INDEPENDENT_CODE`, `None` if not set
+    pub fn get_position_independent_code(&self) -> Option<bool> {
+        self.position_independent_code
+    }
+
+    /// Set `CMAKE_INTERPROCEDURAL_OPTIMIZATION`, enabling LTO when the compiler supports it
+    pub fn interprocedural_optimization(&mut self, enable: bool) -> &mut Self {
+        self.interprocedural_optimization = Some(enable);
+        self
+    }
+
+    /// Get `CMAKE_INTERPROCEDURAL_OPTIMIZATION`, `None` if not set
+    pub fn get_interprocedural_optimization(&self) -> Option<bool> {
+        self.interprocedural_optimization
+    }
+
+    /// Set `CMAKE_VERBOSE_MAKEFILE`
+    pub fn verbose_makefile(&mut self, enable: bool) -> &mut Self {
+        self.verbose_makefile = Some(enable);
+        self
+    }
+
+    /// Get `CMAKE_VERBOSE_MAKEFILE`, `None` if not set
+    pub fn get_verbose_makefile(&self) -> Option<bool> {
+        self.verbose_makefile
+    }
+
+    /// Skip the compiler identification of CMake, defaults to `false`
+    ///
+    /// `CMAKE_<LANG>_COMPILER_ID` is set from the detected compiler family along with
+    /// `CMAKE_<LANG>_COMPILER_FORCED` and the byte order of the target, which speeds up
+    /// configuring cross compilers that need a working link to be identified. CMake then
+    /// doesn't check that the compiler works or detect its version and ABI, so this is mainly
+    /// appropriate for bare metal targets.
+    pub fn assume_compiler_id(&mut self, enable: bool) -> &mut Self {
+        self.assume_compiler_id = enable;
+        self
+    }
+
+    /// Get whether the compiler identification of CMake is skipped
+    pub fn get_assume_compiler_id(&self) -> bool {
+        self.assume_compiler_id
+    }
+
+    /// Add a comment to the header of the toolchain file, like the name of the project
+    ///
+    /// The header always notes the target and the version of this crate that generated the
+    /// file, so toolchain files of different sub-projects can be told apart.
+    pub fn header_comment(&mut self, comment: String) -> &mut Self {
+        self.header_comment = Some(comment);
+        self
+    }
+
+    /// Get the comment added to the header of the toolchain file
+    pub fn get_header_comment(&self) -> Option<&str> {
+        self.header_comment.as_deref()
+    }
+
+    /// Stamp the header of the toolchain file with the time it was generated, defaults to
+    /// `false`
+    ///
+    /// The output isn't reproducible anymore with a timestamp, and
+    /// [`CMakeToolchain::emit_to_out_dir`] rewrites the file every time.
+    pub fn timestamp(&mut self, enable: bool) -> &mut Self {
+        self.timestamp = enable;
+        self
+    }
+
+    /// Get whether the toolchain file is stamped with the time it was generated
+    pub fn get_timestamp(&self) -> bool {
+        self.timestamp
+    }
+
+    /// Set the oldest CMake version the toolchain file has to work with, like `3.5`
+    ///
+    /// Variables the version doesn't support are replaced by compiler flags: the sysroot
+    /// becomes a `--sysroot` flag before CMake 3.9 and the clang target a `--target` flag
+    /// before CMake 3.1. The [`CMakeToolchain::flags_file`] avoids `string(JOIN)` before
+    /// CMake 3.12.
+    pub fn min_cmake_version(&mut self, version: String) -> &mut Self {
+        self.min_cmake_version = Some(version);
+        self
+    }
+
+    /// Get the oldest CMake version the toolchain file has to work with
+    pub fn get_min_cmake_version(&self) -> Option<&str> {
+        self.min_cmake_version.as_deref()
+    }
+
+    /// Whether the minimum CMake version is older than `major.minor`
+    fn cmake_older_than(&self, major: u32, minor: u32) -> bool {
+        let version = match &self.min_cmake_version {
+            Some(version) => version,
+            None => return false,
+        };
+        let mut parts = version.split('.').map(|part| part.parse::<u32>());
+        match (parts.next(), parts.next()) {
+            (Some(Ok(v_major)), Some(Ok(v_minor))) => (v_major, v_minor) < (major, minor),
+            (Some(Ok(v_major)), None) => v_major < major,
+            _ => false,
+        }
+    }
+
+    /// Set CMake generator, like `Ninja` or `Unix Makefiles`
+    ///
+    /// The generator isn't part of the toolchain file, it's applied to a `cmake::Config` by
+    /// `apply_to_cmake_config`, replacing a generator set on the config before.
+    pub fn generator(&mut self, generator: String) -> &mut Self {
+        self.generator = Some(generator);
+        self
+    }
+
+    /// Get CMake generator
+    pub fn get_generator(&self) -> Option<&str> {
+        self.generator.as_deref()
+    }
+
+    /// Include a CMake file before the generated settings, like a vendor toolchain file
+    ///
+    /// Files are included in the order they were added.
+    pub fn include_before(&mut self, path: PathBuf) -> &mut Self {
+        self.include_before.push(path);
+        self
+    }
+
+    /// Get the files included before the generated settings
+    pub fn get_include_before(&self) -> &[PathBuf] {
+        &self.include_before
+    }
+
+    /// Include a CMake file after the generated settings, so it can override them
+    ///
+    /// Files are included in the order they were added, before the custom variables.
+    pub fn include_after(&mut self, path: PathBuf) -> &mut Self {
+        self.include_after.push(path);
+        self
+    }
+
+    /// Get the files included after the generated settings
+    pub fn get_include_after(&self) -> &[PathBuf] {
+        &self.include_after
+    }
+
+    /// Move the compiler and linker flags to a separate CMake file at `path`
+    ///
+    /// Each flag is written on its own line, and the toolchain file includes `path`, which
+    /// keeps lines and `-D` arguments short when there are many flags or long paths, like with
+    /// MSVC on Windows. `path` should be absolute, it's written along with the toolchain file
+    /// by [`CMakeToolchain::write_to_file`] and [`CMakeToolchain::emit_to_out_dir`].
+    ///
+    /// The flags are joined with `string(JOIN)`, which needs CMake 3.12. For an older
+    /// [`CMakeToolchain::min_cmake_version`] they're set as a list and joined with
+    /// `string(REPLACE)`, so a flag can't contain `;` then.
+    pub fn flags_file(&mut self, path: PathBuf) -> &mut Self {
+        self.flags_file = Some(path);
+        self
+    }
+
+    /// Get the file the compiler and linker flags are written to
+    pub fn get_flags_file(&self) -> Option<&Path> {
+        self.flags_file.as_deref()
+    }
+
+    /// Define a custom CMake variable
+    ///
+    /// Custom variables are emitted after the standard ones, and replace a standard variable
+    /// of the same name. The value is taken literally, it's quoted and escaped as needed.
+    pub fn define(&mut self, key: &str, value: &str) -> &mut Self {
+        self.defines.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Get the custom CMake variables
+    pub fn get_defines(&self) -> &BTreeMap<String, String> {
+        &self.defines
+    }
+
+    /// Report how each tool was resolved, useful to debug a misbehaving cross compile
+    pub fn explain(&self) -> ToolchainReport {
+        let asm_source = if self.asm.is_some() {
+            self.sources.get("asm").cloned()
+        } else {
+            self.sources.get("cc").cloned()
+        };
+        let mut tools = vec![
+            self.tool_report("cc", Some(&self.cc)),
+            self.tool_report("cxx", Some(&self.cxx)),
+            ToolReport {
+                name: "asm",
+                path: Some(self.get_asm().to_path_buf()),
+                source: asm_source,
+            },
+            self.tool_report("ar", Some(&self.ar)),
+        ];
+        for (name, path) in [
+            ("fortran", &self.fortran),
+            ("ranlib", &self.ranlib),
+            ("strip", &self.strip),
+            ("nm", &self.nm),
+            ("objcopy", &self.objcopy),
+            ("objdump", &self.objdump),
+            ("rc", &self.rc),
+            ("linker", &self.linker),
+            ("mt", &self.mt),
+        ] {
+            tools.push(self.tool_report(name, path.as_ref()));
+        }
+        ToolchainReport {
+            host: self.host.clone(),
+            target: self.target.clone(),
+            tools,
+        }
+    }
+
+    fn tool_report(&self, name: &'static str, path: Option<&PathBuf>) -> ToolReport {
+        ToolReport {
+            name,
+            path: path.cloned(),
+            source: path.and(self.sources.get(name).cloned()),
+        }
+    }
+
+    fn is_explicit(&self, tool: &str) -> bool {
+        self.sources.get(tool) == Some(&ToolSource::Explicit)
+    }
+
+    /// Whether `tool` is neither set explicitly nor through the `var` environment variable
+    fn is_default(&self, tool: &str, var: &str) -> bool {
+        !self.is_explicit(tool) && self.get_var(var).is_none()
+    }
+
+    fn set_source(&mut self, tool: &str, source: ToolSource) {
+        self.sources.insert(tool.to_string(), source);
+    }
+
+    /// Record the source of a resolved tool and return its path
+    fn resolved(&mut self, tool: &str, found: Option<(PathBuf, ToolSource)>) -> Option<PathBuf> {
+        let (path, source) = found?;
+        let path = match source {
+            ToolSource::Env(_) => path,
+            _ => self.in_toolchain_root(path),
+        };
+        self.set_source(tool, source);
+        Some(path)
+    }
+
+    /// Check that the compilers were detected, that the C and C++ standards are known to CMake
+    /// and that every configured tool exists and is executable
+    ///
+    /// Setters like [`CMakeToolchain::target`] keep the previous tools when no compiler is
+    /// found for the new settings, this reports it.
+    pub fn validate(&self) -> Result<(), Error> {
+        if let Some(source) = &self.resolve_error {
+            return Err(Error::CompilerNotFound {
+                target: self.target.clone(),
+                source: source.clone(),
+            });
+        }
+        for (language, standard, standards) in [
+            ("C", self.c_standard, &C_STANDARDS[..]),
+            ("C++", self.cxx_standard, &CXX_STANDARDS[..]),
+        ] {
+            if let Some(standard) = standard.filter(|standard| !standards.contains(standard)) {
+                return Err(Error::InvalidStandard {
+                    target: self.target.clone(),
+                    language: language.to_string(),
+                    standard,
+                });
+            }
+        }
+        let mut tools = vec![
+            ("cc", Some(self.cc.as_path())),
+            ("cxx", Some(self.cxx.as_path())),
+            ("asm", Some(self.get_asm())),
+            ("ar", Some(self.ar.as_path())),
+        ];
+        for (name, path) in [
+            ("fortran", &self.fortran),
+            ("compiler_launcher", &self.compiler_launcher),
+            ("ranlib", &self.ranlib),
+            ("strip", &self.strip),
+            ("nm", &self.nm),
+            ("objcopy", &self.objcopy),
+            ("objdump", &self.objdump),
+            ("rc", &self.rc),
+            ("linker", &self.linker),
+            ("mt", &self.mt),
+        ] {
+            tools.push((name, path.as_deref()));
+        }
+        for (name, path) in tools {
+            if let Some(path) = path {
+                if find_program(path, &self.search_paths()).is_none() {
+                    return Err(Error::ToolNotFound {
+                        target: self.target.clone(),
+                        tool: name.to_string(),
+                        path: path.to_path_buf(),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Validate the toolchain and freeze it, ending the configuration
+    ///
+    /// See [`CMakeToolchain::validate`] for the checks done.
+    pub fn build(self) -> Result<Resolved, Error> {
+        self.validate()?;
+        Ok(Resolved::new(self))
+    }
+
+    /// Render the CMake toolchain file contents
+    ///
+    /// The output only depends on the toolchain settings: variables are emitted in a fixed
+    /// order, custom variables sorted by name, and lines always end with `\n`. Only a
+    /// [`CMakeToolchain::timestamp`] makes it vary between runs.
+    pub fn to_cmake_string(&self) -> String {
+        self.cmake_writer().finish()
+    }
+
+    /// Get the toolchain settings as CMake variables, for `-D<name>=<value>` arguments
+    ///
+    /// These are the variables [`CMakeToolchain::to_cmake_string`] sets, in the same order,
+    /// ready for [`cmake::Config::define`](https://docs.rs/cmake/latest/cmake/struct.Config.html#method.define).
+    /// Lists are joined with `;`. Included files, like the ones added with
+    /// [`CMakeToolchain::include_before`], can't be passed this way and are left out.
+    ///
+    /// With a [`CMakeToolchain::flags_file`], the flag sets are replaced by
+    /// `CMAKE_PROJECT_INCLUDE_BEFORE` pointing to it.
+    pub fn as_cmake_defines(&self) -> Vec<(String, String)> {
+        self.cmake_writer().into_defines()
+    }
+
+    /// Get the tools as environment variables, for build systems like autotools
+    ///
+    /// `CC`, `CXX`, `AR` and `RANLIB` are followed by the target specific variants the cc
+    /// crate reads, like `CC_aarch64_unknown_linux_gnu`. The compiler launcher comes before
+    /// the compiler path and the compiler arguments after it, zig tools include their
+    /// subcommand. Words with whitespace or quotes are wrapped in double quotes.
+    pub fn env_vars(&self) -> Vec<(String, String)> {
+        let zig_target = zig_target(&self.target);
+        let command =
+            |launcher: Option<&Path>, tool: &Path, subcommand: &[&str], args: &[String]| {
+                let mut command: Vec<String> = launcher
+                    .into_iter()
+                    .chain([tool])
+                    .map(|path| path.to_string_lossy().into_owned())
+                    .collect();
+                if is_zig(tool) {
+                    command.extend(subcommand.iter().map(|arg| arg.to_string()));
+                }
+                command.extend(args.iter().cloned());
+                let words: Vec<String> = command
+                    .iter()
+                    .map(|word| writer::quote_flag(word))
+                    .collect();
+                words.join(" ")
+            };
+        let launcher = self.compiler_launcher.as_deref();
+        let mut tools = vec![
+            (
+                "CC",
+                command(
+                    launcher,
+                    &self.cc,
+                    &["cc", "-target", &zig_target],
+                    &self.cc_args,
+                ),
+            ),
+            (
+                "CXX",
+                command(
+                    launcher,
+                    &self.cxx,
+                    &["c++", "-target", &zig_target],
+                    &self.cxx_args,
+                ),
+            ),
+            ("AR", command(None, &self.ar, &["ar"], &[])),
+        ];
+        if let Some(ranlib) = &self.ranlib {
+            tools.push(("RANLIB", command(None, ranlib, &["ranlib"], &[])));
+        }
+        let target = self.target.replace('-', "_");
+        let mut vars: Vec<(String, String)> = tools
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect();
+        for (name, value) in tools {
+            vars.push((format!("{}_{}", name, target), value));
+        }
+        vars
+    }
+
+    /// Render the contents of the [`CMakeToolchain::flags_file`]
+    ///
+    /// This is empty when no flags file is set.
+    pub fn to_cmake_flags_string(&self) -> String {
+        self.cmake_writer().finish_flags()
+    }
+
+    /// Emit the toolchain settings
+    fn cmake_writer(&self) -> CMakeWriter<'_> {
+        let mut writer = CMakeWriter::new(&self.defines);
+        if let Some(flags_file) = &self.flags_file {
+            writer.flags_file(flags_file, !self.cmake_older_than(3, 12));
+        }
+        writer.comment(&format!(
+            "CMake toolchain file generated for target {} by {} {}",
+            self.target,
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION")
+        ));
+        if let Some(comment) = &self.header_comment {
+            for line in comment.lines() {
+                writer.comment(line);
+            }
+        }
+        if self.timestamp {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default();
+            writer.comment(&format!(
+                "Generated at {} seconds since the Unix epoch",
+                now.as_secs()
+            ));
+        }
+        if let Some(version) = &self.min_cmake_version {
+            writer.comment(&format!("Assumes CMake {} or newer", version));
+        }
+        if let Some(emscripten_root) = &self.emscripten_root {
+            writer.include(&emscripten_root.join("cmake/Modules/Platform/Emscripten.cmake"));
+        }
+        for path in &self.include_before {
+            writer.include(path);
+        }
+        // Setting `CMAKE_SYSTEM_NAME` puts CMake into cross compiling mode
+        if self.is_cross_compiling() {
+            writer.set("CMAKE_SYSTEM_NAME", &self.system_name);
+            writer.set("CMAKE_SYSTEM_PROCESSOR", &self.system_processor);
+            if let Some(system_version) = &self.system_version {
+                writer.set("CMAKE_SYSTEM_VERSION", system_version);
+            }
+        }
+        // Find the 32-bit libraries of the multiarch layout, like `/usr/lib/i386-linux-gnu`
+        if self.is_x86_multilib() {
+            writer.set("CMAKE_LIBRARY_ARCHITECTURE", "i386-linux-gnu");
+        }
+        // `CMAKE_SYSROOT` was added in CMake 3.0 but only reaches the preprocessor since 3.9
+        let sysroot_as_flag = self.sysroot_as_flag || self.cmake_older_than(3, 9);
+        if let Some(sysroot) = &self.sysroot {
+            if !self.cmake_older_than(3, 0) {
+                writer.set_path("CMAKE_SYSROOT", sysroot);
+            }
+        }
+        if self.target.contains("-apple-") {
+            if let Some(osx_sysroot) = self.sysroot.as_ref().or(self.osx_sysroot.as_ref()) {
+                writer.set_path("CMAKE_OSX_SYSROOT", osx_sysroot);
+            }
+            writer.set("CMAKE_OSX_ARCHITECTURES", apple_arch(&self.target));
+            if let Some(deployment_target) = &self.osx_deployment_target {
+                writer.set("CMAKE_OSX_DEPLOYMENT_TARGET", deployment_target);
+            }
+        }
+        writer.set_path("CMAKE_C_COMPILER", &self.cc);
+        writer.set_path("CMAKE_CXX_COMPILER", &self.cxx);
+        if let Some(launcher) = &self.compiler_launcher {
+            writer.set_path("CMAKE_C_COMPILER_LAUNCHER", launcher);
+            writer.set_path("CMAKE_CXX_COMPILER_LAUNCHER", launcher);
+        }
+        writer.set_path("CMAKE_ASM_COMPILER", self.get_asm());
+        if let Some(fortran) = &self.fortran {
+            writer.set_path("CMAKE_Fortran_COMPILER", fortran);
+        }
+        // CMake needs a single executable as the compiler, the zig subcommand is passed as the
+        // first argument
+        for (var, compiler, subcommand) in [
+            ("CMAKE_C_COMPILER_ARG1", &*self.cc, "cc"),
+            ("CMAKE_CXX_COMPILER_ARG1", &*self.cxx, "c++"),
+            ("CMAKE_ASM_COMPILER_ARG1", self.get_asm(), "cc"),
+        ] {
+            if is_zig(compiler) {
+                writer.set(var, subcommand);
+            }
+        }
+        if self.assume_compiler_id {
+            // The byte order is detected along with the compiler, which is skipped
+            let compiler_id = self.compiler_family.cmake_compiler_id();
+            let byte_order = self.endianness().as_str();
+            for lang in ["C", "CXX"] {
+                writer.set(&format!("CMAKE_{}_COMPILER_ID", lang), compiler_id);
+                writer.set(&format!("CMAKE_{}_COMPILER_FORCED", lang), "TRUE");
+                writer.set(&format!("CMAKE_{}_BYTE_ORDER", lang), byte_order);
+            }
+        }
+        // `CMAKE_<LANG>_COMPILER_TARGET` was added in CMake 3.1
+        let target_as_flag = self.cmake_older_than(3, 1);
+        if let Some(compiler_target) = &self.compiler_target {
+            if !target_as_flag {
+                writer.set("CMAKE_C_COMPILER_TARGET", compiler_target);
+                writer.set("CMAKE_CXX_COMPILER_TARGET", compiler_target);
+                writer.set("CMAKE_ASM_COMPILER_TARGET", compiler_target);
+            }
+        }
+        // The compiler driver also gets the flags when linking, selecting the right multilib
+        let arch_flags = self.arch_flags();
+        let c_args = [&arch_flags[..], &self.cc_tool_flags(), &self.cc_args].concat();
+        let cxx_args = [&arch_flags[..], &self.cxx_tool_flags(), &self.cxx_args].concat();
+        for (var, args, flags) in [
+            ("CMAKE_C_FLAGS_INIT", &c_args[..], &self.c_flags),
+            ("CMAKE_CXX_FLAGS_INIT", &cxx_args[..], &self.cxx_flags),
+            ("CMAKE_ASM_FLAGS_INIT", &arch_flags[..], &Vec::new()),
+            ("CMAKE_EXE_LINKER_FLAGS_INIT", &[], &self.exe_linker_flags),
+            (
+                "CMAKE_SHARED_LINKER_FLAGS_INIT",
+                &[],
+                &self.shared_linker_flags,
+            ),
+            (
+                "CMAKE_MODULE_LINKER_FLAGS_INIT",
+                &[],
+                &self.module_linker_flags,
+            ),
+        ] {
+            let mut flags = [args, flags].concat();
+            if is_zig(&self.cc) {
+                flags.push(format!("-target {}", zig_target(&self.target)));
+            }
+            if target_as_flag {
+                if let Some(compiler_target) = &self.compiler_target {
+                    flags.push(format!("--target={}", compiler_target));
+                }
+            }
+            if sysroot_as_flag {
+                if let Some(sysroot) = &self.sysroot {
+                    let flag = format!("--sysroot={}", writer::cmake_path(sysroot));
+                    flags.push(writer::quote_flag(&flag));
+                }
+            }
+            if var == "CMAKE_EXE_LINKER_FLAGS_INIT" && self.musl_static && self.is_musl() {
+                flags.push("-static".to_string());
+            }
+            if !flags.is_empty() {
+                writer.set_flags(var, &flags);
+            }
+        }
+        if self.emit_archiver {
+            writer.set_path("CMAKE_AR", &self.ar);
+            if let Some(ranlib) = &self.ranlib {
+                writer.set_path("CMAKE_RANLIB", ranlib);
+            }
+        }
+        let archive_rules = self.archive_rules();
+        for lang in ["C", "CXX", "ASM"] {
+            for (step, rule) in ["CREATE", "APPEND", "FINISH"].iter().zip(&archive_rules) {
+                if let Some(rule) = rule {
+                    writer.set_string(&format!("CMAKE_{}_ARCHIVE_{}", lang, step), rule);
+                }
+            }
+        }
+        for (var, tool) in [
+            ("CMAKE_STRIP", &self.strip),
+            ("CMAKE_NM", &self.nm),
+            ("CMAKE_OBJCOPY", &self.objcopy),
+            ("CMAKE_OBJDUMP", &self.objdump),
+        ] {
+            if let Some(tool) = tool {
+                writer.set_path(var, tool);
+            }
+        }
+        if let Some(rc) = &self.rc {
+            writer.set_path("CMAKE_RC_COMPILER", rc);
+            writer.set_path("CMAKE_RC_COMPILER_INIT", rc);
+        }
+        for (var, tool) in [("CMAKE_LINKER", &self.linker), ("CMAKE_MT", &self.mt)] {
+            if let Some(tool) = tool {
+                writer.set_path(var, tool);
+            }
+        }
+        // The staging prefix is searched by the find_* commands as is, it isn't re-rooted
+        // under `CMAKE_FIND_ROOT_PATH`, while the install prefix is re-rooted like any other
+        // search prefix, so packages installed to the sysroot are found.
+        if let Some(staging_prefix) = &self.staging_prefix {
+            writer.set_path("CMAKE_STAGING_PREFIX", staging_prefix);
+        }
+        if let Some(install_prefix) = &self.install_prefix {
+            writer.set_path("CMAKE_INSTALL_PREFIX", install_prefix);
+        }
+        let find_root_path = self.get_find_root_path();
+        if self.is_cross_compiling() && !find_root_path.is_empty() {
+            writer.set_paths("CMAKE_FIND_ROOT_PATH", &find_root_path);
+            for (var, mode) in [
+                (
+                    "CMAKE_FIND_ROOT_PATH_MODE_PROGRAM",
+                    self.find_root_path_mode_program,
+                ),
+                (
+                    "CMAKE_FIND_ROOT_PATH_MODE_LIBRARY",
+                    self.find_root_path_mode_library,
+                ),
+                (
+                    "CMAKE_FIND_ROOT_PATH_MODE_INCLUDE",
+                    self.find_root_path_mode_include,
+                ),
+                (
+                    "CMAKE_FIND_ROOT_PATH_MODE_PACKAGE",
+                    self.find_root_path_mode_package,
+                ),
+            ] {
+                writer.set(var, mode.as_str());
+            }
+        }
+        let emulator = self.get_emulator();
+        if !emulator.is_empty() {
+            writer.set_string("CMAKE_CROSSCOMPILING_EMULATOR", &emulator.join(";"));
+        }
+        if let Some(target_type) = self.try_compile_target_type {
+            writer.set("CMAKE_TRY_COMPILE_TARGET_TYPE", target_type.as_str());
+        }
+        for (var, suffix) in [
+            ("CMAKE_EXECUTABLE_SUFFIX", &self.executable_suffix),
+            ("CMAKE_STATIC_LIBRARY_SUFFIX", &self.static_library_suffix),
+            ("CMAKE_SHARED_LIBRARY_SUFFIX", &self.shared_library_suffix),
+        ] {
+            if let Some(suffix) = suffix {
+                writer.set_string(var, suffix);
+            }
+        }
+        for (var, standard) in [
+            ("CMAKE_C_STANDARD", self.c_standard),
+            ("CMAKE_CXX_STANDARD", self.cxx_standard),
+        ] {
+            if let Some(standard) = standard {
+                writer.set(var, &standard.to_string());
+            }
+        }
+        for (var, enable) in [
+            ("CMAKE_C_STANDARD_REQUIRED", self.c_standard_required),
+            ("CMAKE_C_EXTENSIONS", self.c_extensions),
+            ("CMAKE_CXX_STANDARD_REQUIRED", self.cxx_standard_required),
+            ("CMAKE_CXX_EXTENSIONS", self.cxx_extensions),
+            (
+                "CMAKE_POSITION_INDEPENDENT_CODE",
+                self.position_independent_code,
+            ),
+            (
+                "CMAKE_INTERPROCEDURAL_OPTIMIZATION",
+                self.interprocedural_optimization,
+            ),
+            ("CMAKE_VERBOSE_MAKEFILE", self.verbose_makefile),
+        ] {
+            if let Some(enable) = enable {
+                writer.set(var, if enable { "ON" } else { "OFF" });
+            }
+        }
+        if self.get_skip_rpath() {
+            writer.set("CMAKE_SKIP_BUILD_RPATH", "ON");
+            if self.install_rpath.is_none() {
+                writer.set("CMAKE_SKIP_RPATH", "ON");
+            }
+        }
+        if let Some(rpath) = &self.install_rpath {
+            writer.set_string("CMAKE_INSTALL_RPATH", rpath);
+        }
+        for path in &self.include_after {
+            writer.include(path);
+        }
+        writer
+    }
+
+    /// Write the CMake toolchain file to `path`
+    ///
+    /// The generated file can be passed to CMake via `-DCMAKE_TOOLCHAIN_FILE`.
+    ///
+    /// The file is written atomically, concurrent readers see either the previous or the new
+    /// contents but never a partially written file.
+    pub fn write_to_file(&self, path: &Path) -> Result<(), Error> {
+        if let Some(flags_file) = &self.flags_file {
+            self.write_file(flags_file, &self.to_cmake_flags_string())?;
+        }
+        self.write_file(path, &self.to_cmake_string())
+    }
+
+    /// Write `contents` to `path` atomically, see [`write_atomic`]
+    fn write_file(&self, path: &Path, contents: &str) -> Result<(), Error> {
+        write_atomic(path, contents).map_err(|source| Error::Io {
+            target: self.target.clone(),
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// Write the CMake toolchain file to `OUT_DIR` and return its path
+    ///
+    /// The file is named `cmake-toolchain-<target>.cmake`. A hash of its contents and of the
+    /// consulted environment variables is stored next to it in `<file>.hash`, and the file is
+    /// only rewritten when the hash changes, to avoid spurious rebuilds. Cargo is told to rerun
+    /// the build script when the environment variables change, see
+    /// [`CMakeToolchain::emit_rerun_if_env_changed`].
+    pub fn emit_to_out_dir(&self) -> Result<PathBuf, Error> {
+        let out_dir = env::var_os("OUT_DIR").ok_or(Error::OutDirNotSet {
+            source: env::VarError::NotPresent,
+        })?;
+        let path = Path::new(&out_dir).join(format!("cmake-toolchain-{}.cmake", self.target));
+        let mut files = vec![(path.clone(), self.to_cmake_string())];
+        if let Some(flags_file) = &self.flags_file {
+            files.push((flags_file.clone(), self.to_cmake_flags_string()));
+        }
+        let mut hasher = DefaultHasher::new();
+        files.hash(&mut hasher);
+        for var in self.env_var_names() {
+            (&var, env::var_os(&var)).hash(&mut hasher);
+        }
+        let hash = format!("{:016x}\n", hasher.finish());
+        let hash_path = path.with_extension("cmake.hash");
+        let unchanged = files.iter().all(|(path, _)| path.is_file())
+            && fs::read_to_string(&hash_path).is_ok_and(|existing| existing == hash);
+        if !unchanged {
+            for (path, content) in &files {
+                self.write_file(path, content)?;
+            }
+            self.write_file(&hash_path, &hash)?;
+        }
+        self.emit_rerun_if_env_changed();
+        Ok(path)
+    }
+
+    /// Get the environment variables consulted so far, sorted by name
+    ///
+    /// Variables that aren't set are included, setting them could change the toolchain.
+    pub fn get_consulted_env_vars(&self) -> Vec<String> {
+        self.consulted_env.names()
+    }
+
+    /// Print `cargo:rerun-if-env-changed` for the environment variables of the toolchain
+    ///
+    /// These are the variables consulted so far, and every variant of the tool and flag
+    /// variables like `CC`, `CC_<target>` or `TARGET_CFLAGS` for the current target, so
+    /// Cargo reruns the build script when any of them changes. Like any `rerun-if`
+    /// instruction, this stops Cargo from rerunning the build script whenever a file of the
+    /// package changes.
+    pub fn emit_rerun_if_env_changed(&self) {
+        for var in self.env_var_names() {
+            println!("cargo:rerun-if-env-changed={}", var);
+        }
+    }
+
+    /// Environment variables the toolchain consults for the current target, sorted by name
+    fn env_var_names(&self) -> BTreeSet<String> {
+        let mut names: BTreeSet<String> = self.consulted_env.names().into_iter().collect();
+        for var_base in TOOL_ENV_VARS {
+            names.extend(self.var_names(var_base));
+        }
+        names
+    }
+
+    /// Configure a [`cmake::Config`] to use this toolchain
+    ///
+    /// The toolchain file is written to `OUT_DIR` with [`CMakeToolchain::emit_to_out_dir`].
+    ///
+    /// The generator set with [`CMakeToolchain::generator`] is applied unless `CMAKE_GENERATOR`
+    /// is set in the environment. [`cmake::Config`] doesn't expose a generator set before,
+    /// so it's replaced: apply the toolchain first and call [`cmake::Config::generator`]
+    /// afterwards to choose a different generator.
+    #[cfg(feature = "cmake")]
+    pub fn apply_to_cmake_config(&self, cfg: &mut cmake::Config) -> Result<(), Error> {
+        let toolchain_file = self.emit_to_out_dir()?;
+        cfg.define("CMAKE_TOOLCHAIN_FILE", toolchain_file);
+        if let Some(generator) = &self.generator {
+            if self.getenv("CMAKE_GENERATOR").is_none() {
+                cfg.generator(generator);
+            }
+        }
+        Ok(())
+    }
+
+    fn find_system_name(&self) -> String {
+        let target = &self.target;
+        let system_name = if target.ends_with("-ohos") && self.find_ohos_sdk_native().is_some() {
+            // The CMake shipped with the OpenHarmony SDK knows the `OHOS` platform
+            "OHOS"
+        } else if target.contains("android") || target.contains("linux") {
+            "Linux"
+        } else if target.contains("windows") {
+            "Windows"
+        } else if target.contains("darwin") || target.ends_with("-macabi") {
+            "Darwin"
+        } else if target.contains("-apple-ios") {
+            "iOS"
+        } else if target.contains("-apple-tvos") {
+            "tvOS"
+        } else if target.contains("-apple-watchos") {
+            "watchOS"
+        } else if target.contains("-apple-visionos") {
+            "visionOS"
+        } else if target.contains("freebsd") {
+            "FreeBSD"
+        } else if target.contains("openbsd") {
+            "OpenBSD"
+        } else if target.contains("netbsd") {
+            "NetBSD"
+        } else if target.contains("haiku") {
+            "Haiku"
+        } else if target.contains("emscripten") {
+            "Emscripten"
+        } else if target.contains("-wasi") {
+            "WASI"
+        } else if target.contains("solaris") || target.contains("illumos") {
+            "SunOS"
+        } else if target.contains("dragonfly") {
+            "DragonFly"
+        } else if target.contains("fuchsia") {
+            "Fuchsia"
+        } else if target.contains("redox") {
+            "Redox"
+        } else if target.contains("-aix") {
+            "AIX"
+        } else if target.contains("-uefi") {
+            "UEFI"
+        } else if target.contains("-none") || is_bare_wasm(target) {
+            // Bare metal (`*-none-*`) and wasm targets have no operating system
+            "Generic"
+        } else {
+            // Name any other operating system after the triple, like `Hermit` for
+            // `x86_64-unknown-hermit`
+            return match target_lexicon::Triple::from_str(target).map(|t| t.operating_system) {
+                Ok(os)
+                    if !matches!(
+                        os,
+                        target_lexicon::OperatingSystem::Unknown
+                            | target_lexicon::OperatingSystem::None_
+                    ) =>
+                {
+                    let os = os.to_string();
+                    let mut chars = os.chars();
+                    chars
+                        .next()
+                        .map(|first| first.to_uppercase().chain(chars).collect())
+                        .unwrap_or(os)
+                }
+                _ => "Generic".to_string(),
+            };
+        };
+        system_name.to_string()
+    }
+
+    fn find_system_processor(&self) -> String {
+        let arch = self.target.split('-').next().unwrap_or_default();
+        let bsd = ["freebsd", "openbsd", "netbsd"]
+            .iter()
+            .any(|os| self.target.contains(os));
+        let processor = if arch.starts_with("arm") || arch.starts_with("thumb") {
+            "arm"
+        } else if bsd && (arch == "i586" || arch == "i686") {
+            // BSDs report `uname -p` as `i386` for all 32-bit x86 variants
+            "i386"
+        } else if arch == "x86_64"
+            && (self.target.contains("freebsd") || self.target.contains("openbsd"))
+        {
+            "amd64"
+        } else if arch.starts_with("riscv64") {
+            "riscv64"
+        } else if arch.starts_with("riscv32") {
+            "riscv32"
+        } else {
+            match arch {
+                "arm64" => "aarch64",
+                "mipsisa32r6" => "mips",
+                "mipsisa32r6el" => "mipsel",
+                "mipsisa64r6" => "mips64",
+                "mipsisa64r6el" => "mips64el",
+                "powerpc" => "ppc",
+                "powerpc64" => "ppc64",
+                "powerpc64le" => "ppc64le",
+                _ => arch,
+            }
+        };
+        processor.to_string()
+    }
+
+    fn find_ar(&self) -> (PathBuf, ToolSource) {
+        if let Some((var, p)) = self.find_var("AR") {
+            return (p.into(), ToolSource::Env(var));
+        }
+        if let Some(found) = self.find_cargo_config_tool("ar") {
+            return found;
+        }
+        if let Some(found) = self.find_llvm_binutil("ar") {
+            return found;
+        }
+        if let Some(found) = self.find_sibling_tool("ar") {
+            return found;
+        }
+        let target = &self.target;
+        let default_ar = ("ar".to_string(), ToolSource::Default);
+        let (program, source) = if target.contains("android") {
+            let prefix = android_ndk_prefixes(target).1;
+            (
+                format!("{}-ar", prefix),
+                ToolSource::Prefix(prefix.to_string()),
+            )
+        } else if target.contains("emscripten") {
+            ("emar".to_string(), ToolSource::Default)
+        } else if target.ends_with("-gnullvm") || target.ends_with("-ohos") || is_bare_wasm(target)
+        {
+            ("llvm-ar".to_string(), ToolSource::Default)
+        } else if target.contains("msvc") {
+            match cc::windows_registry::find_tool(target, "lib.exe") {
+                Some(t) => return (t.path().to_path_buf(), ToolSource::Detected),
+                None => ("lib.exe".to_string(), ToolSource::Default),
+            }
+        } else if target.contains("illumos") {
+            // The default 'ar' on illumos uses a non-standard flags,
+            // but the OS comes bundled with a GNU-compatible variant.
+            //
+            // Use the GNU-variant to match other Unix systems.
+            ("gar".to_string(), ToolSource::Default)
+        } else if &self.host != target {
+            match self.find_prefixed_tool("ar") {
+                Some(found) => return found,
+                None => default_ar,
+            }
+        } else {
+            default_ar
+        };
+        (program.into(), source)
+    }
+
+    fn find_ranlib(&self) -> Option<(PathBuf, ToolSource)> {
+        if let Some((var, p)) = self.find_var("RANLIB") {
+            return Some((p.into(), ToolSource::Env(var)));
+        }
+        // `lib.exe` style archivers produce indexed archives themselves
+        let ar_name = self.ar.file_stem().and_then(|stem| stem.to_str());
+        if matches!(ar_name, Some("lib" | "llvm-lib")) {
+            return None;
+        }
+        if let Some(found) = self.find_llvm_binutil("ranlib") {
+            return Some(found);
+        }
+        if let Some(found) = self.find_sibling_tool("ranlib") {
+            return Some(found);
+        }
+        let target = &self.target;
+        let default_ranlib = ("ranlib".to_string(), ToolSource::Default);
+        let (program, source) = if target.contains("android") {
+            let prefix = android_ndk_prefixes(target).1;
+            (
+                format!("{}-ranlib", prefix),
+                ToolSource::Prefix(prefix.to_string()),
+            )
+        } else if target.contains("emscripten") {
+            ("emranlib".to_string(), ToolSource::Default)
+        } else if target.ends_with("-gnullvm") || target.ends_with("-ohos") || is_bare_wasm(target)
+        {
+            ("llvm-ranlib".to_string(), ToolSource::Default)
+        } else if target.contains("msvc") {
+            return None;
+        } else if &self.host != target {
+            match self.find_prefixed_tool("ranlib") {
+                Some(found) => return Some(found),
+                None => default_ranlib,
+            }
+        } else {
+            default_ranlib
+        };
+        Some((program.into(), source))
+    }
+
+    /// Find a binutils program like `strip` or `nm`, `None` if it isn't available
+    fn find_binutil(&self, var: &str, tool: &str) -> Option<(PathBuf, ToolSource)> {
+        if let Some((var, p)) = self.find_var(var) {
+            return Some((p.into(), ToolSource::Env(var)));
+        }
+        if let Some(found) = self.find_llvm_binutil(tool) {
+            return Some(found);
+        }
+        if let Some(found) = self.find_sibling_tool(tool) {
+            return Some(found);
+        }
+        let target = &self.target;
+        if target.contains("msvc") || target.contains("emscripten") {
+            return None;
+        }
+        if target.contains("android") {
+            let prefix = android_ndk_prefixes(target).1;
+            let program = format!("{}-{}", prefix, tool);
+            if find_program(Path::new(&program), &self.search_paths()).is_some() {
+                return Some((program.into(), ToolSource::Prefix(prefix.to_string())));
+            }
+        } else if &self.host != target {
+            if let Some(found) = self.find_prefixed_tool(tool) {
+                return Some(found);
+            }
+        }
+        if find_program(Path::new(tool), &self.search_paths()).is_some() {
+            Some((tool.into(), ToolSource::Default))
+        } else {
+            None
+        }
+    }
+
+    fn find_fortran(&self) -> Option<(PathBuf, ToolSource)> {
+        if let Some((var, p)) = self.find_var("FC") {
+            return Some((p.into(), ToolSource::Env(var)));
+        }
+        if let Some(found) = self.find_sibling_tool("gfortran") {
+            return Some(found);
+        }
+        if !self.is_cross_compiling() || self.target.contains("msvc") {
+            return None;
+        }
+        let prefix = self.prefix()?;
+        let program = PathBuf::from(format!("{}-gfortran", prefix));
+        find_program(&program, &self.search_paths()).map(|_| (program, ToolSource::Prefix(prefix)))
+    }
+
+    /// Find `<prefix>-<tool>` on `PATH`, trying every candidate prefix of the target
+    ///
+    /// The compiler of the preferred prefix may be missing while the binutils of another one
+    /// are installed. An explicit prefix is trusted without checking the tool exists.
+    fn find_prefixed_tool(&self, tool: &str) -> Option<(PathBuf, ToolSource)> {
+        let prefix = self.prefix()?;
+        if self.prefix_is_explicit() {
+            let program = format!("{}-{}", prefix, tool);
+            return Some((program.into(), ToolSource::Prefix(prefix)));
+        }
+        let mut prefixes = vec![prefix];
+        for candidate in gnu_prefixes(&self.target) {
+            if !prefixes.iter().any(|prefix| prefix == candidate) {
+                prefixes.push(candidate.to_string());
+            }
+        }
+        let paths = self.search_paths();
+        prefixes
+            .iter()
+            .find_map(|prefix| {
+                let program = PathBuf::from(format!("{}-{}", prefix, tool));
+                find_program(&program, &paths)
+                    .map(|_| (program, ToolSource::Prefix(prefix.clone())))
+            })
+            .or_else(|| {
+                // Some distributions only install versioned tools, like `aarch64-linux-gnu-ar-12`
+                prefixes.into_iter().find_map(|prefix| {
+                    let program = find_versioned_program(&format!("{}-{}", prefix, tool), &paths)?;
+                    Some((program.into(), ToolSource::Prefix(prefix)))
+                })
+            })
+    }
+
+    /// Find the LLVM variant of a binutils program when the LLVM binutils are used
+    fn find_llvm_binutil(&self, tool: &str) -> Option<(PathBuf, ToolSource)> {
+        if !["ar", "ranlib", "strip", "nm"].contains(&tool) || self.target.contains("msvc") {
+            return None;
+        }
+        let forced = match self.llvm_binutils {
+            Some(true) => true,
+            None if self.compiler_family == CompilerFamily::Clang => false,
+            _ => return None,
+        };
+        let llvm_tool = format!("llvm-{}", tool);
+        let mut candidates = Vec::new();
+        // `clang-17` comes with `llvm-ar-17`
+        let cc_version = self
+            .cc
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.rsplit_once('-'))
+            .filter(|(name, version)| {
+                name.ends_with("clang") && version.chars().all(|c| c.is_ascii_digit())
+            })
+            .map(|(_, version)| version.to_string());
+        if let Some(version) = cc_version {
+            candidates.push(format!("{}-{}", llvm_tool, version));
+        }
+        candidates.push(llvm_tool.clone());
+        let paths = self.search_paths();
+        let found = candidates
+            .iter()
+            .find(|candidate| find_program(Path::new(candidate), &paths).is_some())
+            .cloned()
+            .or_else(|| find_versioned_program(&llvm_tool, &paths));
+        let program = match found {
+            Some(program) => program,
+            None if forced => candidates.swap_remove(0),
+            None => return None,
+        };
+        Some((program.into(), ToolSource::Default))
+    }
+
+    /// Find a binutils program in the tool search directory, next to the C compiler
+    ///
+    /// The prefix of the compiler name is reused, so `aarch64-linux-gnu-gcc` finds
+    /// `aarch64-linux-gnu-ar`. LLVM tools like `llvm-ar` are tried as well for clang.
+    fn find_sibling_tool(&self, tool: &str) -> Option<(PathBuf, ToolSource)> {
+        if self.target.contains("msvc") {
+            return None;
+        }
+        let dir = self.get_tool_search_dir()?;
+        let stem = self.cc.file_stem()?.to_str()?;
+        let prefix = ["-gcc", "-clang", "-cc"]
+            .iter()
+            .find_map(|suffix| stem.strip_suffix(suffix));
+        let mut candidates = vec![match prefix {
+            Some(prefix) => format!("{}-{}", prefix, tool),
+            None => tool.to_string(),
+        }];
+        if self.compiler_family == CompilerFamily::Clang {
+            candidates.push(format!("llvm-{}", tool));
+        }
+        candidates.into_iter().find_map(|candidate| {
+            let path = dir.join(format!("{}{}", candidate, env::consts::EXE_SUFFIX));
+            is_executable(&path).then(|| (path, ToolSource::SearchDir(dir.to_path_buf())))
+        })
+    }
+
+    /// Find the linker, configured for Cargo or of the MSVC toolchain
+    fn find_linker(&self) -> Option<(PathBuf, ToolSource)> {
+        if self.target.contains("msvc") {
+            if let Some((var, p)) = self.find_var("LD") {
+                return Some((p.into(), ToolSource::Env(var)));
+            }
+        }
+        if let Some(found) = self.find_cargo_config_tool("linker") {
+            return Some(found);
+        }
+        self.find_msvc_tool("LD", "link.exe")
+    }
+
+    /// Find a `[target.<target>]` tool like `linker` in the Cargo configuration
+    ///
+    /// The configuration is searched from the current directory upwards, like Cargo does.
+    #[cfg(feature = "cargo-config")]
+    fn find_cargo_config_tool(&self, key: &str) -> Option<(PathBuf, ToolSource)> {
+        let dir = env::current_dir().ok()?;
+        let (tool, config) = cargo_config::find_target_setting(&dir, &self.target, key)?;
+        Some((tool, ToolSource::CargoConfig(config)))
+    }
+
+    #[cfg(not(feature = "cargo-config"))]
+    fn find_cargo_config_tool(&self, _key: &str) -> Option<(PathBuf, ToolSource)> {
+        None
+    }
+
+    /// Find a tool of the MSVC toolchain of the target architecture, like `link.exe`
+    fn find_msvc_tool(&self, var: &str, tool: &str) -> Option<(PathBuf, ToolSource)> {
+        if !self.target.contains("msvc") {
+            return None;
+        }
+        if let Some((var, p)) = self.find_var(var) {
+            return Some((p.into(), ToolSource::Env(var)));
+        }
+        cc::windows_registry::find_tool(&self.target, tool)
+            .map(|t| (t.path().to_path_buf(), ToolSource::Detected))
+    }
+
+    /// Find the MinGW resource compiler
+    fn find_rc(&self) -> Option<(PathBuf, ToolSource)> {
+        if self.host == self.target {
+            return self.find_binutil("RC", "windres");
+        }
+        if let Some((var, p)) = self.find_var("RC") {
+            return Some((p.into(), ToolSource::Env(var)));
+        }
+        if let Some(found) = self.find_sibling_tool("windres") {
+            return Some(found);
+        }
+        // Never fall back to the host windres, it produces resources for the wrong architecture
+        let prefix = self.prefix()?;
+        Some((
+            format!("{}-windres", prefix).into(),
+            ToolSource::Prefix(prefix),
+        ))
+    }
+
+    /// Use the LLVM toolchain shipped with the Android NDK, NDK r23 removed GCC and binutils
+    fn find_android_ndk_tools(&mut self) {
+        if !self.target.contains("android") {
+            return;
+        }
+        let bin_dir = match self.find_android_ndk_bin_dir() {
+            Some(bin_dir) => bin_dir,
+            None => return,
+        };
+        let clang_target = format!(
+            "{}{}",
+            android_ndk_prefixes(&self.target).0,
+            self.android_api_level
+        );
+        let clang = format!("{}-clang", clang_target);
+        // The clang wrappers are batch scripts on Windows
+        let script_suffix = if cfg!(windows) { ".cmd" } else { "" };
+        let exe_suffix = env::consts::EXE_SUFFIX;
+        if self.is_default("cc", "CC") {
+            self.cc = bin_dir.join(format!("{}{}", clang, script_suffix));
+            self.set_source("cc", ToolSource::Sdk("Android NDK".to_string()));
+            self.compiler_family = CompilerFamily::Clang;
+            // Keep the API level, clang would otherwise use the NDK's minimum
+            self.compiler_target = Some(clang_target);
+        }
+        if self.is_default("cxx", "CXX") {
+            self.cxx = bin_dir.join(format!("{}++{}", clang, script_suffix));
+            self.set_source("cxx", ToolSource::Sdk("Android NDK".to_string()));
+        }
+        let llvm_tool = |tool: &str| bin_dir.join(format!("llvm-{}{}", tool, exe_suffix));
+        if self.is_default("ar", "AR") {
+            self.ar = llvm_tool("ar");
+            self.set_source("ar", ToolSource::Sdk("Android NDK".to_string()));
+        }
+        if self.is_default("ranlib", "RANLIB") {
+            self.ranlib = Some(llvm_tool("ranlib"));
+            self.set_source("ranlib", ToolSource::Sdk("Android NDK".to_string()));
+        }
+        if self.is_default("strip", "STRIP") {
+            self.strip = Some(llvm_tool("strip"));
+            self.set_source("strip", ToolSource::Sdk("Android NDK".to_string()));
+        }
+        if self.is_default("nm", "NM") {
+            self.nm = Some(llvm_tool("nm"));
+            self.set_source("nm", ToolSource::Sdk("Android NDK".to_string()));
+        }
+        if self.is_default("objcopy", "OBJCOPY") {
+            self.objcopy = Some(llvm_tool("objcopy"));
+            self.set_source("objcopy", ToolSource::Sdk("Android NDK".to_string()));
+        }
+        if self.is_default("objdump", "OBJDUMP") {
+            self.objdump = Some(llvm_tool("objdump"));
+            self.set_source("objdump", ToolSource::Sdk("Android NDK".to_string()));
+        }
+    }
+
+    /// Find the SDK path of Apple targets, from `SDKROOT` like Xcode sets it or from `xcrun`
+    ///
+    /// `SDKROOT` is ignored when it points to the SDK of another platform, like the macOS SDK
+    /// of a build script while targeting iOS.
+    fn find_osx_sysroot(&self) -> Option<PathBuf> {
+        let sdk = apple_sdk_name(&self.target)?;
+        let sdk_root = self
+            .get_var("SDKROOT")
+            .map(PathBuf::from)
+            .filter(|sdk_root| {
+                sdk_root
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().to_lowercase().starts_with(sdk))
+            });
+        sdk_root.or_else(|| xcrun_sdk_path(sdk))
+    }
+
+    /// Use the Emscripten compiler wrappers, from `EMSDK` or `EMSCRIPTEN` when set
+    fn find_emscripten_tools(&mut self) {
+        if !self.target.contains("emscripten") {
+            return;
+        }
+        self.emscripten_root = self.getenv("EMSCRIPTEN").map(PathBuf::from).or_else(|| {
+            self.getenv("EMSDK")
+                .map(|emsdk| Path::new(&emsdk).join("upstream/emscripten"))
+        });
+        // The wrappers are batch scripts on Windows
+        let script_suffix = if cfg!(windows) { ".bat" } else { "" };
+        let emscripten_tool = |tool: &str| {
+            let tool = format!("{}{}", tool, script_suffix);
+            match &self.emscripten_root {
+                Some(root) => root.join(tool),
+                None => PathBuf::from(tool),
+            }
+        };
+        let emscripten_source = match self.emscripten_root {
+            Some(_) => ToolSource::Sdk("Emscripten".to_string()),
+            None => ToolSource::Default,
+        };
+        let cc = emscripten_tool("emcc");
+        let cxx = emscripten_tool("em++");
+        let ar = emscripten_tool("emar");
+        let ranlib = emscripten_tool("emranlib");
+        if self.is_default("cc", "CC") {
+            self.cc = cc;
+            self.set_source("cc", emscripten_source.clone());
+        }
+        if self.is_default("cxx", "CXX") {
+            self.cxx = cxx;
+            self.set_source("cxx", emscripten_source.clone());
+        }
+        if self.is_default("ar", "AR") {
+            self.ar = ar;
+            self.set_source("ar", emscripten_source.clone());
+        }
+        if self.is_default("ranlib", "RANLIB") {
+            self.ranlib = Some(ranlib);
+            self.set_source("ranlib", emscripten_source.clone());
+        }
+    }
+
+    fn is_musl(&self) -> bool {
+        self.target
+            .rsplit('-')
+            .next()
+            .is_some_and(|env| env.starts_with("musl"))
+    }
+
+    /// Find the sysroot of a musl cross toolchain, like musl-cross-make's
+    /// `<root>/x86_64-linux-musl` next to the `<root>/bin` directory of the compiler
+    fn find_musl_root(&self) -> Option<PathBuf> {
+        if !self.is_musl() || !self.is_cross_compiling() {
+            return None;
+        }
+        let prefix = self.prefix()?;
+        let cc = find_program(&self.cc, &self.search_paths())?;
+        let root = cc.parent()?.parent()?.join(prefix);
+        root.join("lib").is_dir().then_some(root)
+    }
+
+    /// Use the clang toolchain from `WASI_SDK_PATH` for WASI targets
+    fn find_wasi_sdk_tools(&mut self) {
+        if !self.target.contains("-wasi") {
+            return;
+        }
+        let exe_suffix = env::consts::EXE_SUFFIX;
+        let wasi_sdk = self.getenv("WASI_SDK_PATH").map(PathBuf::from);
+        let wasi_tool = |tool: &str| {
+            let tool = format!("{}{}", tool, exe_suffix);
+            match &wasi_sdk {
+                Some(wasi_sdk) => wasi_sdk.join("bin").join(tool),
+                None => PathBuf::from(tool),
+            }
+        };
+        let wasi_source = match wasi_sdk {
+            Some(_) => ToolSource::Sdk("WASI SDK".to_string()),
+            None => ToolSource::Default,
+        };
+        let cc = wasi_tool("clang");
+        let cxx = wasi_tool("clang++");
+        let ar = wasi_tool("llvm-ar");
+        let ranlib = wasi_tool("llvm-ranlib");
+        if self.is_default("cc", "CC") {
+            self.cc = cc;
+            self.set_source("cc", wasi_source.clone());
+            self.compiler_family = CompilerFamily::Clang;
+            self.compiler_target = Some(self.llvm_triple());
+        }
+        if self.is_default("cxx", "CXX") {
+            self.cxx = cxx;
+            self.set_source("cxx", wasi_source.clone());
+        }
+        if self.is_default("ar", "AR") {
+            self.ar = ar;
+            self.set_source("ar", wasi_source.clone());
+        }
+        if self.is_default("ranlib", "RANLIB") {
+            self.ranlib = Some(ranlib);
+            self.set_source("ranlib", wasi_source.clone());
+        }
+        // Without the WASI SDK the system clang is told the target via `compiler_target`
+        if let Some(wasi_sdk) = wasi_sdk {
+            if self.sysroot.is_none() {
+                self.sysroot = Some(wasi_sdk.join("share/wasi-sysroot"));
+            }
+        }
+    }
+
+    /// Use the clang toolchain and sysroot of the OpenHarmony SDK for OHOS targets
+    fn find_ohos_sdk_tools(&mut self) {
+        if !self.target.ends_with("-ohos") {
+            return;
+        }
+        let native = match self.find_ohos_sdk_native() {
+            Some(native) => native,
+            None => return,
+        };
+        let ohos_tool = |tool: &str| {
+            native
+                .join("llvm/bin")
+                .join(format!("{}{}", tool, env::consts::EXE_SUFFIX))
+        };
+        let ohos_source = ToolSource::Sdk("OpenHarmony SDK".to_string());
+        if self.is_default("cc", "CC") {
+            self.cc = ohos_tool("clang");
+            self.set_source("cc", ohos_source.clone());
+            self.compiler_family = CompilerFamily::Clang;
+            self.compiler_target = Some(self.llvm_triple());
+        }
+        if self.is_default("cxx", "CXX") {
+            self.cxx = ohos_tool("clang++");
+            self.set_source("cxx", ohos_source.clone());
+        }
+        if self.is_default("ar", "AR") {
+            self.ar = ohos_tool("llvm-ar");
+            self.set_source("ar", ohos_source.clone());
+        }
+        if self.is_default("ranlib", "RANLIB") {
+            self.ranlib = Some(ohos_tool("llvm-ranlib"));
+            self.set_source("ranlib", ohos_source);
+        }
+        if self.sysroot.is_none() {
+            self.sysroot = Some(native.join("sysroot"));
+        }
+    }
+
+    /// Find the `native` directory of the OpenHarmony SDK
+    ///
+    /// It's set directly by `OHOS_SDK_NATIVE`, or found below `OHOS_NDK_HOME`.
+    fn find_ohos_sdk_native(&self) -> Option<PathBuf> {
+        if let Some(native) = self.getenv("OHOS_SDK_NATIVE") {
+            return Some(native.into());
+        }
+        let ndk_home = self.getenv("OHOS_NDK_HOME")?;
+        Some(Path::new(&ndk_home).join("native"))
+    }
+
+    /// Find the `toolchains/llvm/prebuilt/<host-tag>/bin` directory of the Android NDK
+    fn find_android_ndk_bin_dir(&self) -> Option<PathBuf> {
+        let ndk_home = self
+            .getenv("ANDROID_NDK_HOME")
+            .or_else(|| self.getenv("NDK_HOME"))?;
+        let prebuilt = Path::new(&ndk_home).join("toolchains/llvm/prebuilt");
+        let mut host_dirs: Vec<PathBuf> = fs::read_dir(prebuilt)
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path().join("bin"))
+            .filter(|bin_dir| bin_dir.is_dir())
+            .collect();
+        host_dirs.sort();
+        host_dirs.into_iter().next()
+    }
+
+    /// Read a whitespace separated list of flags from the environment
+    fn find_flags(&self, var: &str) -> Vec<String> {
+        self.get_var(var)
+            .map(|flags| flags.split_whitespace().map(String::from).collect())
+            .unwrap_or_default()
+    }
+
+    fn getenv(&self, v: &str) -> Option<String> {
+        self.consulted_env.record(v);
+        std::env::var(v).ok()
+    }
+
+    fn get_var(&self, var_base: &str) -> Option<String> {
+        self.find_var(var_base).map(|(_, value)| value)
+    }
+
+    /// Like `get_var`, but also returns the name of the variable that was set
+    fn find_var(&self, var_base: &str) -> Option<(String, String)> {
+        // FIXME: use Result
+        self.var_names(var_base)
+            .into_iter()
+            .find_map(|var| self.getenv(&var).map(|value| (var, value)))
+    }
+
+    /// Environment variable names consulted for `var_base`, in order of precedence
+    fn var_names(&self, var_base: &str) -> Vec<String> {
+        let target = &self.target;
+        let host = &self.host;
+        let kind = if host == target { "HOST" } else { "TARGET" };
+        let target_u = target.replace('-', "_");
+        // Environment variable names can't contain dots, like in `thumbv8m.main-none-eabi`
+        let target_sanitized = target.replace(['-', '.'], "_");
+        let mut names = vec![
+            format!("{}_{}", var_base, target),
+            format!("{}_{}", var_base, target_u),
+        ];
+        if target_sanitized != target_u {
+            names.push(format!("{}_{}", var_base, target_sanitized));
+        }
+        names.push(format!("{}_{}", kind, var_base));
+        names.push(var_base.to_string());
+        names
+    }
+
+    /// Cross compile prefix of the target, resolved once and shared by all tools
+    fn prefix(&self) -> Option<String> {
+        self.prefix
+            .get_or_init(|| {
+                self.prefix_override
+                    .clone()
+                    .or_else(|| self.prefix_for_target(&self.target))
+            })
+            .clone()
+    }
+
+    /// Whether the cross compile prefix was given explicitly, it's trusted without probing
+    fn prefix_is_explicit(&self) -> bool {
+        self.prefix_override.is_some() || self.getenv("CROSS_COMPILE").is_some()
+    }
+
+    fn prefix_for_target(&self, target: &str) -> Option<String> {
+        // CROSS_COMPILE is of the form: "arm-linux-gnueabi-"
+        let cc_env = self.getenv("CROSS_COMPILE");
+        let cross_compile = cc_env.as_ref().map(|s| s.trim_end_matches('-').to_owned());
+        cross_compile.or_else(|| {
+            find_working_gnu_prefix(
+                gnu_prefixes(target),
+                &self.get_path_exe_suffixes(),
+                &self.search_paths(),
+            )
+            .map(|x| x.to_owned())
+        })
+    }
+}
+
+/// Map a Rust target to the prefix of its GNU cross toolchain, like `aarch64-linux-gnu`
+///
+/// Unlike the toolchain this doesn't consult the `CROSS_COMPILE` environment variable. For
+/// targets with several canonical prefixes the first one found on `PATH` is returned.
+pub fn gnu_prefix(target: &str) -> Option<String> {
+    find_working_gnu_prefix(
+        gnu_prefixes(target),
+        &default_exe_suffixes(),
+        &path_entries(),
+    )
+    .map(|x| x.to_owned())
+}
+
+/// Define the GNU cross toolchain prefixes of Rust targets, along with the list of the targets
+macro_rules! gnu_prefixes {
+    ($($target:literal => $prefixes:expr,)*) => {
+        /// Rust targets with known GNU cross toolchain prefixes
+        const SUPPORTED_TARGETS: &[&str] = &[$($target),*];
+
+        /// Candidate GNU cross toolchain prefixes of a Rust target, from most to least likely
+        fn gnu_prefixes(target: &str) -> &'static [&'static str] {
+            match target {
+                $($target => $prefixes,)*
+                _ => &[],
+            }
+        }
+    };
+}
+
+gnu_prefixes! {
+    "aarch64-pc-windows-gnu" => &["aarch64-w64-mingw32"],
+    "aarch64-pc-windows-gnullvm" => &["aarch64-w64-mingw32"],
+    "aarch64-uwp-windows-gnu" => &["aarch64-w64-mingw32"],
+    "aarch64-unknown-linux-gnu" => &["aarch64-linux-gnu"],
+    "aarch64-unknown-linux-musl" => &["aarch64-linux-musl"],
+    "aarch64-unknown-linux-ohos" => &["aarch64-linux-ohos"],
+    "aarch64-unknown-netbsd" => &["aarch64--netbsd"],
+    "arm-unknown-linux-gnueabi" => &["arm-linux-gnueabi"],
+    "armv4t-unknown-linux-gnueabi" => &["arm-linux-gnueabi"],
+    "armv5te-unknown-linux-gnueabi" => &["arm-linux-gnueabi"],
+    "armv5te-unknown-linux-musleabi" => &["arm-linux-gnueabi"],
+    "arm-frc-linux-gnueabi" => &["arm-frc-linux-gnueabi"],
+    "arm-unknown-linux-gnueabihf" => &["arm-linux-gnueabihf"],
+    "arm-unknown-linux-musleabi" => &["arm-linux-musleabi"],
+    "arm-unknown-linux-musleabihf" => &["arm-linux-musleabihf"],
+    "arm-unknown-netbsd-eabi" => &["arm--netbsdelf-eabi"],
+    "armv6-unknown-netbsd-eabihf" => &["armv6--netbsdelf-eabihf"],
+    "armv7-unknown-linux-gnueabi" => &["arm-linux-gnueabi"],
+    "armv7-unknown-linux-gnueabihf" => &["arm-linux-gnueabihf"],
+    "armv7-unknown-linux-musleabi" => &["arm-linux-musleabi"],
+    "armv7-unknown-linux-musleabihf" => &["arm-linux-musleabihf"],
+    "armv7-unknown-linux-ohos" => &["arm-linux-ohos"],
+    "armv7neon-unknown-linux-gnueabihf" => &["arm-linux-gnueabihf"],
+    "armv7neon-unknown-linux-musleabihf" => &["arm-linux-musleabihf"],
+    "thumbv7-unknown-linux-gnueabihf" => &["arm-linux-gnueabihf"],
+    "thumbv7-unknown-linux-musleabihf" => &["arm-linux-musleabihf"],
+    "thumbv7neon-unknown-linux-gnueabihf" => &["arm-linux-gnueabihf"],
+    "thumbv7neon-unknown-linux-musleabihf" => &["arm-linux-musleabihf"],
+    "armv7-unknown-netbsd-eabihf" => &["armv7--netbsdelf-eabihf"],
+    "aarch64-unknown-freebsd" => &["aarch64-unknown-freebsd"],
+    "armv6-unknown-freebsd" => &["armv6-unknown-freebsd-gnueabihf"],
+    "armv7-unknown-freebsd" => &["armv7-unknown-freebsd-gnueabihf"],
+    "i686-unknown-freebsd" => &["i686-unknown-freebsd"],
+    "powerpc64-unknown-freebsd" => &["powerpc64-unknown-freebsd"],
+    "powerpc64le-unknown-freebsd" => &["powerpc64le-unknown-freebsd"],
+    "riscv64gc-unknown-freebsd" => &["riscv64-unknown-freebsd"],
+    "x86_64-unknown-freebsd" => &["x86_64-unknown-freebsd"],
+    "aarch64-unknown-openbsd" => &["aarch64-unknown-openbsd"],
+    "aarch64-unknown-redox" => &["aarch64-unknown-redox"],
+    "i686-unknown-openbsd" => &["i686-unknown-openbsd"],
+    "x86_64-unknown-openbsd" => &["x86_64-unknown-openbsd"],
+    "hexagon-unknown-linux-musl" => &["hexagon-linux-musl"],
+    "i586-unknown-linux-musl" => &["musl"],
+    "i686-pc-windows-gnu" => &["i686-w64-mingw32"],
+    "i686-pc-windows-gnullvm" => &["i686-w64-mingw32"],
+    "i686-uwp-windows-gnu" => &["i686-w64-mingw32"],
+    "i686-unknown-linux-gnu" => &[
+        "i686-linux-gnu",
+        "x86_64-linux-gnu", // transparently support gcc-multilib
+    ],
+    "i686-unknown-linux-musl" => &["musl"],
+    "i686-unknown-netbsd" => &["i486--netbsdelf"],
+    "i686-unknown-haiku" => &["i586-pc-haiku"],
+    "i686-unknown-redox" => &["i686-unknown-redox"],
+    "loongarch64-unknown-linux-gnu" => &["loongarch64-linux-gnu"],
+    "loongarch64-unknown-linux-musl" => &["loongarch64-linux-musl"],
+    "mips-unknown-linux-gnu" => &["mips-linux-gnu"],
+    "mips-unknown-linux-musl" => &["mips-linux-musl"],
+    "mipsel-unknown-linux-gnu" => &["mipsel-linux-gnu"],
+    "mipsel-unknown-linux-musl" => &["mipsel-linux-musl"],
+    "mips64-unknown-linux-gnuabi64" => &["mips64-linux-gnuabi64"],
+    "mips64-unknown-linux-muslabi64" => &["mips64-linux-musl"],
+    "mips64el-unknown-linux-gnuabi64" => &["mips64el-linux-gnuabi64"],
+    "mips64el-unknown-linux-muslabi64" => &["mips64el-linux-musl"],
+    "mipsisa32r6-unknown-linux-gnu" => &["mipsisa32r6-linux-gnu"],
+    "mipsisa32r6el-unknown-linux-gnu" => &["mipsisa32r6el-linux-gnu"],
+    "mipsisa64r6-unknown-linux-gnuabi64" => &["mipsisa64r6-linux-gnuabi64"],
+    "mipsisa64r6el-unknown-linux-gnuabi64" => &["mipsisa64r6el-linux-gnuabi64"],
+    "powerpc-unknown-linux-gnu" => &["powerpc-linux-gnu"],
+    "powerpc-unknown-linux-gnuspe" => &["powerpc-linux-gnuspe"],
+    "powerpc-unknown-linux-musl" => &["powerpc-linux-musl"],
+    "powerpc-unknown-netbsd" => &["powerpc--netbsd"],
+    "powerpc64-unknown-linux-gnu" => &["powerpc-linux-gnu"],
+    "powerpc64-unknown-linux-musl" => &["powerpc64-linux-musl"],
+    "powerpc64le-unknown-linux-gnu" => &["powerpc64le-linux-gnu"],
+    "powerpc64le-unknown-linux-musl" => &["powerpc64le-linux-musl"],
+    "riscv32i-unknown-none-elf" => &[
+        "riscv32-unknown-elf",
+        "riscv64-unknown-elf",
+        "riscv-none-embed",
+    ],
+    "riscv32imac-unknown-none-elf" => &[
+        "riscv32-unknown-elf",
+        "riscv64-unknown-elf",
+        "riscv-none-embed",
+    ],
+    "riscv32imc-unknown-none-elf" => &[
+        "riscv32-unknown-elf",
+        "riscv64-unknown-elf",
+        "riscv-none-embed",
+    ],
+    "riscv64gc-unknown-none-elf" => &[
+        "riscv64-unknown-elf",
+        "riscv32-unknown-elf",
+        "riscv-none-embed",
+    ],
+    "riscv64imac-unknown-none-elf" => &[
+        "riscv64-unknown-elf",
+        "riscv32-unknown-elf",
+        "riscv-none-embed",
+    ],
+    "riscv64gc-unknown-linux-gnu" => &["riscv64-linux-gnu"],
+    "riscv32gc-unknown-linux-gnu" => &["riscv32-linux-gnu"],
+    "riscv64gc-unknown-linux-musl" => &["riscv64-linux-musl"],
+    "riscv32gc-unknown-linux-musl" => &["riscv32-linux-musl"],
+    "s390x-unknown-linux-gnu" => &["s390x-linux-gnu"],
+    "s390x-unknown-linux-musl" => &["s390x-linux-musl"],
+    "sparc-unknown-linux-gnu" => &["sparc-linux-gnu"],
+    "sparc64-unknown-linux-gnu" => &["sparc64-linux-gnu"],
+    "sparc64-unknown-netbsd" => &["sparc64--netbsd"],
+    "sparcv9-sun-solaris" => &["sparcv9-sun-solaris"],
+    "armv7a-none-eabi" => &["arm-none-eabi"],
+    "armv7a-none-eabihf" => &["arm-none-eabi"],
+    "armebv7r-none-eabi" => &["arm-none-eabi"],
+    "armebv7r-none-eabihf" => &["arm-none-eabi"],
+    "armv7r-none-eabi" => &["arm-none-eabi"],
+    "armv7r-none-eabihf" => &["arm-none-eabi"],
+    "thumbv6m-none-eabi" => &["arm-none-eabi"],
+    "thumbv7em-none-eabi" => &["arm-none-eabi"],
+    "thumbv7em-none-eabihf" => &["arm-none-eabi"],
+    "thumbv7m-none-eabi" => &["arm-none-eabi"],
+    "thumbv8m.base-none-eabi" => &["arm-none-eabi"],
+    "thumbv8m.main-none-eabi" => &["arm-none-eabi"],
+    "thumbv8m.main-none-eabihf" => &["arm-none-eabi"],
+    "x86_64-pc-windows-gnu" => &["x86_64-w64-mingw32"],
+    "x86_64-pc-windows-gnullvm" => &["x86_64-w64-mingw32"],
+    "x86_64-uwp-windows-gnu" => &["x86_64-w64-mingw32"],
+    "x86_64-rumprun-netbsd" => &["x86_64-rumprun-netbsd"],
+    "x86_64-unknown-linux-gnu" => &["x86_64-linux-gnu"],
+    "x86_64-unknown-linux-musl" => &["musl"],
+    "x86_64-unknown-linux-ohos" => &["x86_64-linux-ohos"],
+    "x86_64-unknown-netbsd" => &["x86_64--netbsd"],
+    "x86_64-unknown-haiku" => &["x86_64-unknown-haiku"],
+    "x86_64-unknown-redox" => &["x86_64-unknown-redox"],
+}
+
+/// Get the Rust targets with a known GNU cross toolchain prefix, see [`gnu_prefix`]
+pub fn supported_targets() -> &'static [&'static str] {
+    SUPPORTED_TARGETS
+}
+
+/// Some platforms have multiple, compatible, canonical prefixes. Look through
+/// each possible prefix for a compiler that exists and return it. The prefixes
+/// should be ordered from most-likely to least-likely. Each of the executable `extensions`
+/// is tried for the compiler name in each of the `paths` searched.
+fn find_working_gnu_prefix(
+    prefixes: &[&'static str],
+    extensions: &[&str],
+    paths: &[PathBuf],
+) -> Option<&'static str> {
+    // let suffix = if self.cpp { "-g++" } else { "-gcc" };
+    let suffix = "-gcc";
+
+    // Loop through PATH entries searching for each toolchain. This ensures that we
+    // are more likely to discover the toolchain early on, because chances are good
+    // that the desired toolchain is in one of the higher-priority paths.
+    paths
+        .iter()
+        .find_map(|path_entry| {
+            for prefix in prefixes {
+                for extension in extensions {
+                    let target_compiler = format!("{}{}{}", prefix, suffix, extension);
+                    if path_entry.join(&target_compiler).exists() {
+                        return Some(prefix);
+                    }
+                }
+            }
+            None
+        })
+        .copied()
+        .or_else(|| {
+            // Some distributions only install versioned compilers, like `aarch64-linux-gnu-gcc-12`
+            prefixes
+                .iter()
+                .find(|prefix| {
+                    find_versioned_program(&format!("{}{}", prefix, suffix), paths).is_some()
+                })
+                .copied()
+        })
+        .or_else(||
+        // If no toolchain was found, provide the first toolchain that was passed in.
+        // This toolchain has been shown not to exist, however it will appear in the
+        // error that is shown to the user which should make it easier to search for
+        // where it should be obtained.
+        prefixes.first().copied())
+}
+
+/// Executable suffixes tried for compilers on `PATH`: the host's, none and `.exe`
+fn default_exe_suffixes() -> Vec<&'static str> {
+    let mut suffixes = vec![env::consts::EXE_SUFFIX];
+    for suffix in ["", ".exe"] {
+        if !suffixes.contains(&suffix) {
+            suffixes.push(suffix);
+        }
+    }
+    suffixes
+}
+
+/// Map a Rust target to the target name zig understands, like `arm-linux-gnueabihf`
+///
+/// Zig drops the vendor, calls macOS `macos` and bare metal targets `freestanding`.
+pub fn zig_target(target: &str) -> String {
+    let mut parts = target.split('-');
+    let arch = parts.next().unwrap_or_default();
+    let arch = if arch.starts_with("riscv64") {
+        "riscv64"
+    } else if arch.starts_with("riscv32") {
+        "riscv32"
+    } else if arch.starts_with("thumb") {
+        "thumb"
+    } else if arch.starts_with("armeb") {
+        "armeb"
+    } else if arch.starts_with("arm") && arch != "arm64" {
+        "arm"
+    } else {
+        match arch {
+            "arm64" => "aarch64",
+            "i386" | "i586" | "i686" => "x86",
+            _ => arch,
+        }
+    };
+    let vendors = [
+        "unknown", "pc", "apple", "uwp", "sun", "wrs", "fortanix", "nvidia",
+    ];
+    let mut rest: Vec<&str> = parts.collect();
+    if rest.len() > 1 && vendors.contains(&rest[0]) {
+        rest.remove(0);
+    }
+    let mut zig = arch.to_string();
+    for (i, part) in rest.into_iter().enumerate() {
+        let part = match (i, part) {
+            (0, "darwin") => "macos",
+            (0, "none") => "freestanding",
+            (0, "wasip1") => "wasi",
+            (_, "sim") => "simulator",
+            _ => part,
+        };
+        zig.push('-');
+        zig.push_str(part);
+    }
+    zig
+}
+
+/// Whether `path` is the `zig` binary
+fn is_zig(path: &Path) -> bool {
+    path.file_stem().is_some_and(|stem| stem == "zig")
+}
+
+/// Split a `CC` style value like `ccache clang -m32` into launcher, compiler and arguments
+///
+/// Values naming an existing file are taken as is, so paths with spaces keep working. The
+/// zig `subcommand`, like `cc` of `zig cc`, isn't an argument, it's added back when emitting.
+fn split_compiler(value: &str, subcommand: &str) -> (Option<PathBuf>, PathBuf, Vec<String>) {
+    if Path::new(value).is_file() {
+        return (None, value.into(), Vec::new());
+    }
+    let mut parts = value.split_whitespace();
+    let first = match parts.next() {
+        Some(first) => first,
+        None => return (None, value.into(), Vec::new()),
+    };
+    let is_launcher = Path::new(first)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .is_some_and(|stem| {
+            ["ccache", "sccache", "distcc", "cachepot", "buildcache"].contains(&stem)
+        });
+    let (launcher, compiler) = match parts.clone().next() {
+        Some(compiler) if is_launcher => {
+            parts.next();
+            (Some(PathBuf::from(first)), compiler)
+        }
+        _ => (None, first),
+    };
+    let mut args: Vec<String> = parts.map(String::from).collect();
+    if is_zig(Path::new(compiler)) && args.first().is_some_and(|arg| arg == subcommand) {
+        args.remove(0);
+    }
+    (launcher, compiler.into(), args)
+}
+
+/// Replace a missing compiler like `aarch64-linux-gnu-gcc` with the newest installed version
+/// like `aarch64-linux-gnu-gcc-12`, using the C++ compiler of the same version
+fn versioned_compilers(cc: PathBuf, cxx: PathBuf, paths: &[PathBuf]) -> (PathBuf, PathBuf) {
+    if cc.components().count() > 1 || find_program(&cc, paths).is_some() {
+        return (cc, cxx);
+    }
+    let cc_name = cc.to_string_lossy().into_owned();
+    let versioned_cc = match find_versioned_program(&cc_name, paths) {
+        Some(versioned_cc) => versioned_cc,
+        None => return (cc, cxx),
+    };
+    let version = &versioned_cc[cc_name.len() + 1..];
+    let versioned_cxx = PathBuf::from(format!("{}-{}", cxx.display(), version));
+    let cxx =
+        if find_program(&cxx, paths).is_none() && find_program(&versioned_cxx, paths).is_some() {
+            versioned_cxx
+        } else {
+            cxx
+        };
+    (versioned_cc.into(), cxx)
+}
+
+/// Find the newest `<program>-<version>` in `paths`, like `llvm-ar-17`
+fn find_versioned_program(program: &str, paths: &[PathBuf]) -> Option<String> {
+    let prefix = format!("{}-", program);
+    paths
+        .iter()
+        .filter_map(|path_entry| fs::read_dir(path_entry).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| is_executable(&entry.path()))
+        .filter_map(|entry| {
+            let file_name = entry.file_name().into_string().ok()?;
+            let name = file_name.strip_suffix(env::consts::EXE_SUFFIX)?;
+            let version: u32 = name.strip_prefix(&prefix)?.parse().ok()?;
+            Some((version, name.to_string()))
+        })
+        .max()
+        .map(|(_, name)| name)
+}
+
+/// Write `contents` to a temporary file next to `path` and rename it into place
+fn write_atomic(path: &Path, contents: &str) -> io::Result<()> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let file_name = path.file_name().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is not a file path", path.display()),
+        )
+    })?;
+    let tmp_path = path.with_file_name(format!(
+        ".{}.{}.{}.tmp",
+        file_name.to_string_lossy(),
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::write(&tmp_path, contents)?;
+    let mut result = fs::rename(&tmp_path, path);
+    // Windows refuses to replace a file another process has open, like a concurrent rename
+    // of the same file, which is usually resolved after a moment
+    for _ in 0..10 {
+        match &result {
+            Err(err) if cfg!(windows) && err.kind() == io::ErrorKind::PermissionDenied => {
+                std::thread::sleep(Duration::from_millis(10));
+                result = fs::rename(&tmp_path, path);
+            }
+            _ => break,
+        }
+    }
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+/// Target flags the cc crate invokes `compiler` with, like `-m64` or `-march=...`
+///
+/// Optimization, debug info and warning flags are left to CMake, and flags already passed
+/// through `flags` are skipped. Nothing is returned when `compiler` isn't the detected one.
+fn cc_tool_flags(tool: Option<&cc::Tool>, compiler: &Path, flags: &[String]) -> Vec<String> {
+    let tool = match tool {
+        Some(tool) if tool.path() == compiler && !tool.is_like_msvc() => tool,
+        _ => return Vec::new(),
+    };
+    tool.args()
+        .iter()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .filter(|arg| {
+            let build_flag = ["-O", "-g", "-W"].iter().any(|p| arg.starts_with(p)) || arg == "-w";
+            !build_flag && !flags.contains(arg)
+        })
+        .collect()
+}
+
+/// Find an executable program, searching `paths` for bare program names
+fn find_program(program: &Path, paths: &[PathBuf]) -> Option<PathBuf> {
+    if program.components().count() > 1 {
+        return is_executable(program).then(|| program.to_path_buf());
+    }
+    let exe = format!("{}{}", program.to_string_lossy(), env::consts::EXE_SUFFIX);
+    paths.iter().find_map(|path_entry| {
+        [path_entry.join(program), path_entry.join(&exe)]
+            .into_iter()
+            .find(|candidate| is_executable(candidate))
+    })
+}
+
+/// Entries of the `PATH` environment variable
+fn path_entries() -> Vec<PathBuf> {
+    env::var_os("PATH")
+        .map(|path| env::split_paths(&path).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::metadata(path)
+        .map(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// Default `(cpu, fpu, float_abi)` of Cortex-M targets
+fn cortex_m_defaults(
+    target: &str,
+) -> Option<(&'static str, Option<&'static str>, Option<&'static str>)> {
+    let defaults = match target {
+        "thumbv6m-none-eabi" => ("cortex-m0", None, None),
+        "thumbv7m-none-eabi" => ("cortex-m3", None, None),
+        "thumbv7em-none-eabi" => ("cortex-m4", None, None),
+        "thumbv7em-none-eabihf" => ("cortex-m4", Some("fpv4-sp-d16"), Some("hard")),
+        "thumbv8m.base-none-eabi" => ("cortex-m23", None, None),
+        "thumbv8m.main-none-eabi" => ("cortex-m33", None, None),
+        "thumbv8m.main-none-eabihf" => ("cortex-m33", Some("fpv5-sp-d16"), Some("hard")),
+        _ => return None,
+    };
+    Some(defaults)
+}
+
+/// Map the architecture of a Linux target to the name of its qemu user mode emulator
+fn qemu_arch(target: &str) -> Option<&str> {
+    if !target.contains("linux") {
+        return None;
+    }
+    let arch = target.split('-').next().unwrap_or_default();
+    let qemu_arch = if arch.starts_with("riscv64") {
+        "riscv64"
+    } else if arch.starts_with("riscv32") {
+        "riscv32"
+    } else if arch.starts_with("armeb") {
+        "armeb"
+    } else if arch.starts_with("arm") || arch.starts_with("thumb") {
+        "arm"
+    } else {
+        match arch {
+            "i586" | "i686" => "i386",
+            "powerpc" => "ppc",
+            "powerpc64" => "ppc64",
+            "powerpc64le" => "ppc64le",
+            "sparc64" | "sparcv9" => "sparc64",
+            _ => arch,
+        }
+    };
+    Some(qemu_arch)
+}
+
+/// Whether `target` is a wasm target without an operating system or C library, like
+/// `wasm32-unknown-unknown`
+fn is_bare_wasm(target: &str) -> bool {
+    target.starts_with("wasm") && target.ends_with("-unknown-unknown")
+}
+
+/// Whether a MIPS target uses the soft float ABI, like Rust's musl, uClibc and bare metal
+/// 32-bit MIPS targets
+fn mips_soft_float(target: &str) -> bool {
+    let arch = target.split('-').next().unwrap_or_default();
+    if !arch.starts_with("mips") || arch.starts_with("mips64") || arch.starts_with("mipsisa") {
+        return false;
+    }
+    target.ends_with("-musl")
+        || target.contains("uclibc")
+        || target.contains("-none")
+        || target.contains("-sony-")
+}
+
+/// Map a Rust android target to its Android NDK tool prefixes, as `(clang, binutils)`
+///
+/// The NDK clang wrappers are named like `armv7a-linux-androideabi21-clang`, while the
+/// GNU binutils shipped by NDKs before r23 are named like `arm-linux-androideabi-ar`.
+fn android_ndk_prefixes(target: &str) -> (&str, &str) {
+    match target {
+        "arm-linux-androideabi" | "armv7-linux-androideabi" | "thumbv7neon-linux-androideabi" => {
+            ("armv7a-linux-androideabi", "arm-linux-androideabi")
+        }
+        _ => (target, target),
+    }
+}
+
+/// Map an Apple target to its Xcode SDK name
+fn apple_sdk_name(target: &str) -> Option<&'static str> {
+    let simulator = target.ends_with("-sim")
+        || target.starts_with("x86_64-apple-ios")
+        || target.starts_with("i386-apple-ios");
+    let sdk = if target.contains("-apple-darwin") || target.ends_with("-macabi") {
+        "macosx"
+    } else if target.contains("-apple-ios") {
+        if simulator {
+            "iphonesimulator"
+        } else {
+            "iphoneos"
+        }
+    } else if target.contains("-apple-tvos") {
+        if simulator {
+            "appletvsimulator"
+        } else {
+            "appletvos"
+        }
+    } else if target.contains("-apple-watchos") {
+        if simulator {
+            "watchsimulator"
+        } else {
+            "watchos"
+        }
+    } else if target.contains("-apple-visionos") {
+        if simulator {
+            "xrsimulator"
+        } else {
+            "xros"
+        }
+    } else {
+        return None;
+    };
+    Some(sdk)
+}
+
+/// Ask `xcrun` for the path of `sdk`, cached since `xcrun` is slow
+fn xcrun_sdk_path(sdk: &'static str) -> Option<PathBuf> {
+    static SDK_PATHS: OnceLock<Mutex<BTreeMap<&'static str, Option<PathBuf>>>> = OnceLock::new();
+    let mut sdk_paths = SDK_PATHS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|err| err.into_inner());
+    sdk_paths
+        .entry(sdk)
+        .or_insert_with(|| {
+            let output = Command::new("xcrun")
+                .args(["--sdk", sdk, "--show-sdk-path"])
+                .output()
+                .ok()?;
+            if !output.status.success() {
+                return None;
+            }
+            let sdk_path = String::from_utf8(output.stdout).ok()?;
+            let sdk_path = sdk_path.trim();
+            if sdk_path.is_empty() {
+                None
+            } else {
+                Some(sdk_path.into())
+            }
+        })
+        .clone()
+}
+
+/// Map an Apple target to the environment variable holding its deployment target
+fn apple_deployment_target_var(target: &str) -> Option<&'static str> {
+    let var = if target.contains("-apple-darwin") {
+        "MACOSX_DEPLOYMENT_TARGET"
+    } else if target.contains("-apple-ios") {
+        "IPHONEOS_DEPLOYMENT_TARGET"
+    } else if target.contains("-apple-tvos") {
+        "TVOS_DEPLOYMENT_TARGET"
+    } else if target.contains("-apple-watchos") {
+        "WATCHOS_DEPLOYMENT_TARGET"
+    } else if target.contains("-apple-visionos") {
+        "XROS_DEPLOYMENT_TARGET"
+    } else {
+        return None;
+    };
+    Some(var)
+}
+
+/// Map an Apple target to its `CMAKE_OSX_ARCHITECTURES` value
+fn apple_arch(target: &str) -> &str {
+    let arch = target.split('-').next().unwrap_or_default();
+    match arch {
+        "aarch64" => "arm64",
+        "i686" => "i386",
+        _ => arch,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+    use std::fs;
+    use std::panic::{self, AssertUnwindSafe};
+    use std::path::Path;
+    use std::sync::Mutex;
+
+    use super::{
+        CMakeToolchain, CompilerFamily, Endianness, Error, Resolved, ToolSource,
+        TryCompileTargetType,
+    };
+
+    /// Serializes tests that modify the process environment
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    /// Run `f` with the environment variables `vars` set
+    fn with_env<T>(vars: &[(&str, &str)], f: impl FnOnce() -> T) -> T {
+        let _guard = ENV_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+        let saved: Vec<_> = vars.iter().map(|(key, _)| env::var_os(key)).collect();
+        for (key, value) in vars {
+            env::set_var(key, value);
+        }
+        let result = panic::catch_unwind(AssertUnwindSafe(f));
+        for ((key, _), value) in vars.iter().zip(saved) {
+            match value {
+                Some(value) => env::set_var(key, value),
+                None => env::remove_var(key),
+            }
+        }
+        result.unwrap_or_else(|err| panic::resume_unwind(err))
+    }
+
+    /// A directory `name` in the temporary directory, unique to this test process
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        env::temp_dir().join(format!("{}-{}", name, std::process::id()))
+    }
+
+    /// A temporary directory of dummy executables, removed on drop
+    #[cfg(unix)]
+    struct FakeToolchain {
+        bin_dir: std::path::PathBuf,
+    }
+
+    #[cfg(unix)]
+    impl FakeToolchain {
+        /// Create the directory `name` with an empty shell script for each of `tools`
+        fn new(name: &str, tools: &[&str]) -> Self {
+            let bin_dir = env::temp_dir().join(name);
+            fs::create_dir_all(&bin_dir).unwrap();
+            let toolchain = Self { bin_dir };
+            for tool in tools {
+                toolchain.script(tool, "");
+            }
+            toolchain
+        }
+
+        /// Add the executable `tool` running the shell commands `body`
+        fn script(&self, tool: &str, body: &str) -> std::path::PathBuf {
+            use std::os::unix::fs::PermissionsExt;
+
+            let path = self.bin_dir.join(tool);
+            fs::write(&path, format!("#!/bin/sh\n{}", body)).unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+            path
+        }
+
+        /// Run `f` with the directory prepended to `PATH` and `vars` set
+        fn with_path<T>(&self, vars: &[(&str, &str)], f: impl FnOnce() -> T) -> T {
+            let path = env::join_paths(
+                [self.bin_dir.clone()]
+                    .into_iter()
+                    .chain(env::split_paths(&env::var_os("PATH").unwrap())),
+            )
+            .unwrap();
+            let mut vars = vars.to_vec();
+            vars.push(("PATH", path.to_str().unwrap()));
+            with_env(&vars, f)
+        }
+    }
+
+    #[cfg(unix)]
+    impl Drop for FakeToolchain {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.bin_dir);
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_fake_toolchain() {
+        let fake = FakeToolchain::new(
+            "cmake-toolchain-test-fake-toolchain",
+            &[
+                "aarch64-linux-gnu-gcc",
+                "aarch64-linux-gnu-ar",
+                "aarch64-linux-gnu-ranlib",
+                "riscv-none-embed-gcc",
+            ],
+        );
+        let toolchain = fake.with_path(&[], || {
+            CMakeToolchain::new_without_probe("aarch64-unknown-linux-gnu")
+        });
+        assert_eq!(toolchain.get_cc(), Path::new("aarch64-linux-gnu-gcc"));
+        assert_eq!(toolchain.get_ar(), Path::new("aarch64-linux-gnu-ar"));
+        assert_eq!(
+            toolchain.get_ranlib(),
+            Some(Path::new("aarch64-linux-gnu-ranlib"))
+        );
+        let paths = [fake.bin_dir.clone()];
+        let found = super::find_program(toolchain.get_ar(), &paths);
+        assert_eq!(found, Some(fake.bin_dir.join("aarch64-linux-gnu-ar")));
+
+        let prefixes = ["riscv64-unknown-elf", "riscv-none-embed"];
+        let prefix = super::find_working_gnu_prefix(&prefixes, &[""], &paths);
+        assert_eq!(prefix, Some("riscv-none-embed"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_binutils_not_run() {
+        let fake = FakeToolchain::new("cmake-toolchain-test-binutils-not-run", &[]);
+        let marker = fake.bin_dir.join("ran");
+        fake.script("objcopy", &format!("touch {}\n", marker.display()));
+        let meta = rustc_version::version_meta().unwrap();
+        let toolchain = fake.with_path(&[], || CMakeToolchain::new_without_probe(&meta.host));
+        assert_eq!(toolchain.get_objcopy(), Some(Path::new("objcopy")));
+        assert!(!marker.exists());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_path_exe_suffixes() {
+        // A mingw compiler with `.exe` suffix, like on a WSL mounted Windows path
+        let fake = FakeToolchain::new(
+            "cmake-toolchain-test-exe-suffixes",
+            &["riscv-none-embed-gcc.exe"],
+        );
+        let prefixes = ["riscv64-unknown-elf", "riscv-none-embed"];
+        let paths = [fake.bin_dir.clone()];
+        let suffixes = super::default_exe_suffixes();
+        let prefix = super::find_working_gnu_prefix(&prefixes, &suffixes, &paths);
+        assert_eq!(prefix, Some("riscv-none-embed"));
+        let prefix = super::find_working_gnu_prefix(&prefixes, &[""], &paths);
+        assert_eq!(prefix, Some("riscv64-unknown-elf"));
+
+        let mut toolchain = fake.with_path(&[], || {
+            CMakeToolchain::new_without_probe("riscv64gc-unknown-none-elf")
+        });
+        assert_eq!(toolchain.get_cc(), Path::new("riscv-none-embed-gcc"));
+        fake.with_path(&[], || toolchain.path_exe_suffixes(vec![String::new()]));
+        assert_eq!(toolchain.get_path_exe_suffixes(), [""]);
+        assert_eq!(toolchain.get_cc(), Path::new("riscv64-unknown-elf-gcc"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_toolchain_root() {
+        // The toolchain `bin` directory isn't on `PATH`
+        let fake = FakeToolchain::new(
+            "cmake-toolchain-test-root/bin",
+            &[
+                "riscv-none-embed-gcc",
+                "riscv-none-embed-g++",
+                "riscv-none-embed-ar",
+                "riscv-none-embed-strip",
+            ],
+        );
+        let root = fake.bin_dir.parent().unwrap().to_path_buf();
+        let path = env::var_os("PATH");
+        let mut toolchain = with_env(&[], || {
+            let mut toolchain = CMakeToolchain::new_without_probe("riscv64gc-unknown-none-elf");
+            toolchain.toolchain_root(root.clone());
+            toolchain
+        });
+        assert_eq!(env::var_os("PATH"), path);
+        assert_eq!(toolchain.get_toolchain_root(), Some(root.as_path()));
+        assert_eq!(
+            toolchain.get_cc(),
+            fake.bin_dir.join("riscv-none-embed-gcc")
+        );
+        assert_eq!(
+            toolchain.get_cxx(),
+            fake.bin_dir.join("riscv-none-embed-g++")
+        );
+        assert_eq!(toolchain.get_ar(), fake.bin_dir.join("riscv-none-embed-ar"));
+        assert_eq!(
+            toolchain.get_strip(),
+            Some(fake.bin_dir.join("riscv-none-embed-strip").as_path())
+        );
+        assert!(toolchain.validate().is_ok());
+
+        // Explicit tools are kept as they are
+        with_env(&[], || {
+            toolchain.cc("gcc".into()).toolchain_root(root.clone());
+        });
+        assert_eq!(toolchain.get_cc(), Path::new("gcc"));
+        drop(fake);
+        let _ = fs::remove_dir(&root);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_x86_multilib() {
+        // Only the x86_64 compiler is installed, with gcc-multilib
+        let fake = FakeToolchain::new(
+            "cmake-toolchain-test-x86-multilib",
+            &["x86_64-linux-gnu-gcc", "x86_64-linux-gnu-g++"],
+        );
+        let toolchain = fake.with_path(&[], || {
+            let mut toolchain = CMakeToolchain::new_without_probe("i686-unknown-linux-gnu");
+            toolchain.host("x86_64-unknown-linux-gnu".to_string());
+            toolchain
+        });
+        assert_eq!(toolchain.get_cc(), Path::new("x86_64-linux-gnu-gcc"));
+        assert_eq!(toolchain.get_system_processor(), "i686");
+        let content = toolchain.to_cmake_string();
+        for lang in ["C", "CXX", "ASM"] {
+            assert!(
+                content.contains(&format!("set(CMAKE_{}_FLAGS_INIT \"-m32\")\n", lang)),
+                "{}",
+                lang
+            );
+        }
+        assert!(content.contains("set(CMAKE_SYSTEM_PROCESSOR i686)\n"));
+        assert!(content.contains("set(CMAKE_LIBRARY_ARCHITECTURE i386-linux-gnu)\n"));
+
+        let toolchain = with_env(
+            &[("CC_i686-unknown-linux-gnu", "i686-linux-gnu-gcc")],
+            || CMakeToolchain::new_without_probe("i686-unknown-linux-gnu"),
+        );
+        assert!(!toolchain.to_cmake_string().contains("-m32"));
+    }
+
+    #[test]
+    fn test_cmake_toolchain_for_host() {
+        let meta = rustc_version::version_meta().unwrap();
+        let host = meta.host;
+        let toolchain = with_env(&[], || CMakeToolchain::new(&host));
+        println!("{:#?}", toolchain);
+    }
+
+    #[test]
+    #[cfg(not(target_env = "msvc"))]
+    fn test_cxx_compiler_for_host() {
+        let meta = rustc_version::version_meta().unwrap();
+        let toolchain = with_env(&[], || CMakeToolchain::new(&meta.host));
+        assert_ne!(toolchain.get_cc(), toolchain.get_cxx());
+        assert_eq!(toolchain.get_cc_tool().unwrap().path(), toolchain.get_cc());
+        assert_eq!(
+            toolchain.get_cxx_tool().unwrap().path(),
+            toolchain.get_cxx()
+        );
+        let flags = toolchain.get_c_flags();
+        let tool_flags = super::cc_tool_flags(toolchain.get_cc_tool(), toolchain.get_cc(), flags);
+        for flag in &tool_flags {
+            assert!(!flag.starts_with("-O") && flag != "-w", "{}", flag);
+            assert!(!flags.contains(flag), "{}", flag);
+        }
+        let content = toolchain.to_cmake_string();
+        if !tool_flags.is_empty() {
+            assert!(content.contains(&tool_flags.join(" ")), "{}", content);
+        }
+    }
+
+    #[test]
+    fn test_cross_compile_prefix() {
+        let toolchain = with_env(&[("CROSS_COMPILE", "arm-linux-gnueabihf-")], || {
+            CMakeToolchain::new("armv7-unknown-linux-gnueabihf")
+        });
+        assert_eq!(toolchain.get_ar(), Path::new("arm-linux-gnueabihf-ar"));
+        assert_eq!(
+            toolchain.get_ranlib(),
+            Some(Path::new("arm-linux-gnueabihf-ranlib"))
+        );
+        assert_eq!(
+            toolchain.get_strip(),
+            Some(Path::new("arm-linux-gnueabihf-strip"))
+        );
+    }
+
+    #[test]
+    fn test_prefix_override() {
+        let toolchain = with_env(&[], || {
+            let mut toolchain = CMakeToolchain::new("x86_64-unknown-redox");
+            toolchain.prefix_override("x86_64-redox-".to_string());
+            toolchain
+        });
+        assert_eq!(toolchain.get_prefix_override(), Some("x86_64-redox"));
+        assert_eq!(toolchain.get_ar(), Path::new("x86_64-redox-ar"));
+        assert_eq!(toolchain.get_nm(), Some(Path::new("x86_64-redox-nm")));
+    }
+
+    #[test]
+    fn test_ar_ranlib_env_override() {
+        let toolchain = with_env(
+            &[
+                ("AR_aarch64_unknown_linux_gnu", "/opt/tc/bin/my-ar"),
+                ("RANLIB_aarch64-unknown-linux-gnu", "/opt/tc/bin/my-ranlib"),
+            ],
+            || CMakeToolchain::new("aarch64-unknown-linux-gnu"),
+        );
+        assert_eq!(toolchain.get_ar(), Path::new("/opt/tc/bin/my-ar"));
+        assert_eq!(
+            toolchain.get_ranlib(),
+            Some(Path::new("/opt/tc/bin/my-ranlib"))
+        );
+    }
+
+    #[test]
+    fn test_prefix_for_target() {
+        let toolchain = with_env(&[], || {
+            CMakeToolchain::new(&rustc_version::version_meta().unwrap().host)
+        });
+        with_env(&[], || {
+            for (target, prefix) in [
+                ("aarch64-unknown-linux-gnu", "aarch64-linux-gnu"),
+                ("aarch64-unknown-linux-ohos", "aarch64-linux-ohos"),
+                ("armv7-unknown-linux-musleabi", "arm-linux-musleabi"),
+                ("loongarch64-unknown-linux-gnu", "loongarch64-linux-gnu"),
+                ("powerpc64-unknown-linux-musl", "powerpc64-linux-musl"),
+                ("s390x-unknown-linux-musl", "s390x-linux-musl"),
+                ("x86_64-pc-windows-gnu", "x86_64-w64-mingw32"),
+                ("x86_64-unknown-freebsd", "x86_64-unknown-freebsd"),
+                ("aarch64-unknown-openbsd", "aarch64-unknown-openbsd"),
+                ("x86_64-unknown-haiku", "x86_64-unknown-haiku"),
+                ("x86_64-unknown-redox", "x86_64-unknown-redox"),
+            ] {
+                assert_eq!(
+                    toolchain.prefix_for_target(target).as_deref(),
+                    Some(prefix),
+                    "{}",
+                    target
+                );
+                assert_eq!(super::gnu_prefix(target).as_deref(), Some(prefix));
+            }
+        });
+    }
+
+    #[test]
+    fn test_supported_targets() {
+        let targets = super::supported_targets();
+        assert!(targets.contains(&"aarch64-unknown-linux-gnu"));
+        assert!(targets.contains(&"x86_64-pc-windows-gnullvm"));
+        assert!(!targets.contains(&"x86_64-pc-windows-msvc"));
+        for target in targets {
+            assert!(!super::gnu_prefixes(target).is_empty(), "{}", target);
+        }
+    }
+
+    #[test]
+    fn test_no_ranlib_for_lib_archivers() {
+        let toolchain = with_env(&[], || CMakeToolchain::new("x86_64-pc-windows-msvc"));
+        assert_eq!(toolchain.get_ranlib(), None);
+        assert!(!toolchain.to_cmake_string().contains("CMAKE_RANLIB"));
+
+        let toolchain = with_env(&[("AR_x86_64-pc-windows-gnu", "llvm-lib")], || {
+            CMakeToolchain::new("x86_64-pc-windows-gnu")
+        });
+        assert_eq!(toolchain.get_ranlib(), None);
+    }
+
+    #[test]
+    fn test_cc_env_override() {
+        let toolchain = with_env(
+            &[
+                ("CC_aarch64-unknown-linux-gnu", "/opt/tc/bin/aarch64-gcc"),
+                ("CXX_aarch64_unknown_linux_gnu", "/opt/tc/bin/aarch64-g++"),
+            ],
+            || CMakeToolchain::new("aarch64-unknown-linux-gnu"),
+        );
+        assert_eq!(toolchain.get_cc(), Path::new("/opt/tc/bin/aarch64-gcc"));
+        assert_eq!(toolchain.get_cxx(), Path::new("/opt/tc/bin/aarch64-g++"));
+    }
+
+    #[test]
+    fn test_host_override() {
+        let toolchain = with_env(
+            &[
+                ("HOST_AR", "/opt/host/bin/ar"),
+                ("TARGET_AR", "/opt/target/bin/ar"),
+            ],
+            || {
+                let mut toolchain = CMakeToolchain::new("aarch64-unknown-linux-gnu");
+                toolchain.host("aarch64-unknown-linux-gnu".to_string());
+                toolchain
+            },
+        );
+        assert_eq!(toolchain.get_host(), "aarch64-unknown-linux-gnu");
+        assert_eq!(toolchain.get_ar(), Path::new("/opt/host/bin/ar"));
+    }
+
+    #[test]
+    fn test_retarget() {
+        let meta = rustc_version::version_meta().unwrap();
+        let toolchain = with_env(
+            &[("AR_aarch64-unknown-linux-gnu", "/opt/tc/bin/ar")],
+            || {
+                let mut toolchain = CMakeToolchain::new(&meta.host);
+                toolchain.target("aarch64-unknown-linux-gnu".to_string());
+                toolchain
+            },
+        );
+        assert_eq!(toolchain.get_target(), "aarch64-unknown-linux-gnu");
+        assert_eq!(toolchain.get_system_name(), "Linux");
+        assert_eq!(toolchain.get_system_processor(), "aarch64");
+        assert_eq!(toolchain.get_ar(), Path::new("/opt/tc/bin/ar"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_is_available() {
+        let meta = rustc_version::version_meta().unwrap();
+        assert!(with_env(&[], || CMakeToolchain::is_available(&meta.host)));
+        let available = with_env(
+            &[("CC_aarch64-unknown-linux-gnu", "/nonexistent/bin/gcc")],
+            || CMakeToolchain::is_available("aarch64-unknown-linux-gnu"),
+        );
+        assert!(!available);
+        let available = with_env(&[("CC_aarch64-unknown-linux-gnu", "false")], || {
+            CMakeToolchain::is_available("aarch64-unknown-linux-gnu")
+        });
+        assert!(!available);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_detect_sysroot() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = env::temp_dir().join("cmake-toolchain-test-detect-sysroot");
+        let sysroot = root.join("aarch64-linux-gnu/libc");
+        fs::create_dir_all(&sysroot).unwrap();
+        fs::create_dir_all(root.join("bin")).unwrap();
+        let gcc = root.join("bin/aarch64-linux-gnu-gcc");
+        fs::write(&gcc, format!("#!/bin/sh\necho {}\n", sysroot.display())).unwrap();
+        fs::set_permissions(&gcc, fs::Permissions::from_mode(0o755)).unwrap();
+        let clang = root.join("bin/clang");
+        fs::write(&clang, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&clang, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut toolchain = with_env(
+            &[("CC_aarch64-unknown-linux-gnu", gcc.to_str().unwrap())],
+            || CMakeToolchain::new_without_probe("aarch64-unknown-linux-gnu"),
+        );
+        toolchain.detect_sysroot();
+        assert_eq!(toolchain.get_sysroot(), Some(sysroot.as_path()));
+
+        // llvm-mingw style `<root>/<prefix>` next to `<root>/bin/clang`
+        let prefix_dir = root.join("aarch64-linux-gnu");
+        fs::create_dir_all(prefix_dir.join("include")).unwrap();
+        let mut toolchain = with_env(
+            &[("CC_aarch64-unknown-linux-gnu", clang.to_str().unwrap())],
+            || CMakeToolchain::new_without_probe("aarch64-unknown-linux-gnu"),
+        );
+        toolchain.detect_sysroot();
+        assert_eq!(toolchain.get_sysroot(), Some(prefix_dir.as_path()));
+
+        // Nothing found leaves the sysroot unchanged
+        toolchain
+            .cc("/nonexistent/bin/gcc".into())
+            .sysroot("/opt/sysroot".into())
+            .detect_sysroot();
+        assert_eq!(toolchain.get_sysroot(), Some(Path::new("/opt/sysroot")));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_new_without_probe() {
+        let toolchain = with_env(&[], || {
+            CMakeToolchain::new_without_probe("aarch64-unknown-linux-gnu")
+        });
+        assert_eq!(toolchain.get_cc(), Path::new("aarch64-linux-gnu-gcc"));
+        assert_eq!(toolchain.get_cxx(), Path::new("aarch64-linux-gnu-g++"));
+        assert_eq!(toolchain.get_probe_duration(), None);
+        assert!(toolchain.get_cc_tool().is_none());
+
+        let toolchain = with_env(&[("CC_aarch64-unknown-linux-gnu", "clang")], || {
+            CMakeToolchain::new_without_probe("aarch64-unknown-linux-gnu")
+        });
+        assert_eq!(toolchain.get_compiler_family(), CompilerFamily::Clang);
+        let toolchain = with_env(&[], || CMakeToolchain::new("aarch64-unknown-linux-gnu"));
+        assert!(toolchain.get_probe_duration().is_some());
+    }
+
+    #[test]
+    fn test_update_tools() {
+        let mut toolchain = with_env(&[], || CMakeToolchain::new("aarch64-unknown-linux-gnu"));
+        let probe_duration = toolchain.get_probe_duration();
+        with_env(&[], || {
+            toolchain
+                .prefix_override("aarch64-none-linux-gnu".to_string())
+                .use_llvm_binutils(false);
+        });
+        // Only a new host or target runs the cc crate again
+        assert_eq!(toolchain.get_probe_duration(), probe_duration);
+        assert_eq!(toolchain.get_ar(), Path::new("aarch64-none-linux-gnu-ar"));
+
+        // A failed eager update leaves the toolchain unchanged
+        let cc = toolchain.get_cc().to_path_buf();
+        let err = with_env(&[], || {
+            toolchain.try_target("nonsense".to_string()).map(|_| ())
+        });
+        assert!(matches!(err, Err(Error::CompilerNotFound { .. })));
+        assert_eq!(toolchain.get_target(), "aarch64-unknown-linux-gnu");
+        assert_eq!(toolchain.get_system_name(), "Linux");
+        assert_eq!(toolchain.get_cc(), cc);
+        assert!(!matches!(
+            toolchain.validate(),
+            Err(Error::CompilerNotFound { .. })
+        ));
+
+        // Otherwise the setting is kept and the failure is reported by validate
+        with_env(&[], || {
+            toolchain.target("nonsense".to_string());
+        });
+        assert_eq!(toolchain.get_target(), "nonsense");
+        assert_eq!(toolchain.get_cc(), cc);
+        assert!(matches!(
+            toolchain.validate(),
+            Err(Error::CompilerNotFound { .. })
+        ));
+        with_env(&[], || {
+            toolchain.target("aarch64-unknown-linux-gnu".to_string());
+        });
+        assert!(!matches!(
+            toolchain.validate(),
+            Err(Error::CompilerNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_from_cc_build() {
+        let meta = rustc_version::version_meta().unwrap();
+        let mut build = cc::Build::new();
+        build.opt_level(2).compiler("/opt/tc/bin/my-cc");
+        let toolchain = with_env(&[], || CMakeToolchain::from_cc_build(&meta.host, &build));
+        assert_eq!(toolchain.get_cc(), Path::new("/opt/tc/bin/my-cc"));
+        assert_eq!(toolchain.get_cxx(), Path::new("/opt/tc/bin/my-cc"));
+    }
+
+    #[test]
+    fn test_probing_flags_not_emitted() {
+        let meta = rustc_version::version_meta().unwrap();
+        let mut build = cc::Build::new();
+        build.opt_level(0).debug(false);
+        let toolchain = with_env(&[], || CMakeToolchain::from_cc_build(&meta.host, &build));
+        let content = toolchain.to_cmake_string();
+        assert!(!content.contains("-O0"));
+        assert!(!content.contains("-g0"));
+
+        let vars = [("OPT_LEVEL", "3"), ("DEBUG", "true")];
+        let toolchain = with_env(&vars, || CMakeToolchain::new(&meta.host));
+        let tool = toolchain.get_cc_tool().unwrap();
+        if !tool.is_like_msvc() {
+            assert!(tool.args().iter().any(|arg| arg == "-O3"));
+        }
+        let content = toolchain.to_cmake_string();
+        assert!(!content.contains("-O3"));
+        assert!(!content.contains(" -g"));
+    }
+
+    #[test]
+    fn test_target_and_host_cc_env() {
+        let vars = [
+            ("TARGET_CC", "/opt/target/bin/cc"),
+            ("TARGET_CXX", "/opt/target/bin/c++"),
+            ("HOST_CC", "/opt/host/bin/cc"),
+        ];
+        let toolchain = with_env(&vars, || CMakeToolchain::new("aarch64-unknown-linux-gnu"));
+        assert_eq!(toolchain.get_cc(), Path::new("/opt/target/bin/cc"));
+        assert_eq!(toolchain.get_cxx(), Path::new("/opt/target/bin/c++"));
+        assert_eq!(
+            toolchain.explain().tools[0].source,
+            Some(ToolSource::Env("TARGET_CC".to_string()))
+        );
+
+        let meta = rustc_version::version_meta().unwrap();
+        let toolchain = with_env(&vars, || CMakeToolchain::new(&meta.host));
+        assert_eq!(toolchain.get_cc(), Path::new("/opt/host/bin/cc"));
+    }
+
+    #[test]
+    fn test_cc_env_with_args() {
+        let toolchain = with_env(
+            &[
+                ("CC_aarch64-unknown-linux-gnu", "ccache clang"),
+                ("CXX_aarch64-unknown-linux-gnu", "clang++ -fsome -m64"),
+            ],
+            || CMakeToolchain::new("aarch64-unknown-linux-gnu"),
+        );
+        assert_eq!(toolchain.get_cc(), Path::new("clang"));
+        assert_eq!(toolchain.get_compiler_launcher(), Some(Path::new("ccache")));
+        assert_eq!(toolchain.get_cxx(), Path::new("clang++"));
+        let content = toolchain.to_cmake_string();
+        // The cc crate's target flags come first
+        assert!(content.contains(" -fsome -m64\")\n"), "{}", content);
+        assert_eq!(content.matches("-fsome").count(), 1);
+
+        let mut toolchain = with_env(&[], || {
+            CMakeToolchain::new_without_probe("aarch64-unknown-linux-gnu")
+        });
+        toolchain
+            .cc_command("sccache aarch64-linux-gnu-gcc -mcpu=cortex-a53")
+            .cxx_command("zig c++ -fno-exceptions");
+        assert_eq!(toolchain.get_cc(), Path::new("aarch64-linux-gnu-gcc"));
+        assert_eq!(
+            toolchain.get_compiler_launcher(),
+            Some(Path::new("sccache"))
+        );
+        assert_eq!(toolchain.get_cxx(), Path::new("zig"));
+        let content = toolchain.to_cmake_string();
+        assert!(content.contains("-mcpu=cortex-a53\")\n"), "{}", content);
+        assert!(
+            content.contains("set(CMAKE_CXX_COMPILER_ARG1 c++)\n"),
+            "{}",
+            content
+        );
+        assert!(content.contains("-fno-exceptions\")\n"), "{}", content);
+        assert!(!content.contains("c++ -fno-exceptions"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_sibling_tools() {
+        let fake = FakeToolchain::new(
+            "cmake-toolchain-test-sibling-tools",
+            &[
+                "aarch64-linux-gnu-gcc",
+                "aarch64-linux-gnu-ar",
+                "aarch64-linux-gnu-strip",
+            ],
+        );
+        let bin_dir = &fake.bin_dir;
+        let cc = bin_dir.join("aarch64-linux-gnu-gcc");
+        let toolchain = with_env(
+            &[("CC_aarch64-unknown-linux-gnu", cc.to_str().unwrap())],
+            || CMakeToolchain::new("aarch64-unknown-linux-gnu"),
+        );
+        assert_eq!(toolchain.get_tool_search_dir(), Some(bin_dir.as_path()));
+        assert_eq!(toolchain.get_ar(), bin_dir.join("aarch64-linux-gnu-ar"));
+        assert_eq!(
+            toolchain.get_strip(),
+            Some(bin_dir.join("aarch64-linux-gnu-strip").as_path())
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_musl_static() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = env::temp_dir().join("cmake-toolchain-test-musl");
+        let musl_root = root.join("aarch64-linux-musl");
+        fs::create_dir_all(root.join("bin")).unwrap();
+        fs::create_dir_all(musl_root.join("lib")).unwrap();
+        let cc = root.join("bin/aarch64-linux-musl-gcc");
+        fs::write(&cc, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&cc, fs::Permissions::from_mode(0o755)).unwrap();
+        let mut toolchain = with_env(
+            &[("CC_aarch64-unknown-linux-musl", cc.to_str().unwrap())],
+            || CMakeToolchain::new_without_probe("aarch64-unknown-linux-musl"),
+        );
+        assert!(toolchain.get_musl_static());
+        assert_eq!(toolchain.get_find_root_path(), vec![musl_root.as_path()]);
+        let content = toolchain.to_cmake_string();
+        assert!(content.contains("set(CMAKE_EXE_LINKER_FLAGS_INIT \"-static\")\n"));
+        assert!(!content.contains("CMAKE_SHARED_LINKER_FLAGS_INIT"));
+
+        toolchain.musl_static(false);
+        assert!(toolchain.get_find_root_path().is_empty());
+        assert!(!toolchain.to_cmake_string().contains("-static"));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_fortran() {
+        let toolchain = with_env(&[], || {
+            CMakeToolchain::new_without_probe("thumbv7em-none-eabihf")
+        });
+        assert!(!toolchain
+            .to_cmake_string()
+            .contains("CMAKE_Fortran_COMPILER"));
+
+        let mut toolchain = with_env(
+            &[(
+                "FC_aarch64-unknown-linux-gnu",
+                "/opt/tc/bin/aarch64-linux-gnu-gfortran",
+            )],
+            || CMakeToolchain::new_without_probe("aarch64-unknown-linux-gnu"),
+        );
+        assert_eq!(
+            toolchain.get_fortran(),
+            Some(Path::new("/opt/tc/bin/aarch64-linux-gnu-gfortran"))
+        );
+        assert!(toolchain
+            .to_cmake_string()
+            .contains("set(CMAKE_Fortran_COMPILER \"/opt/tc/bin/aarch64-linux-gnu-gfortran\")\n"));
+        toolchain.fortran("flang-new".into());
+        assert_eq!(toolchain.get_fortran(), Some(Path::new("flang-new")));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_ar_from_other_prefix() {
+        // Only the binutils of the last candidate prefix are installed, without a compiler
+        let fake = FakeToolchain::new(
+            "cmake-toolchain-test-other-prefix",
+            &["riscv-none-embed-ar", "riscv-none-embed-ranlib"],
+        );
+        let toolchain = fake.with_path(&[], || {
+            CMakeToolchain::new_without_probe("riscv64gc-unknown-none-elf")
+        });
+        assert_eq!(toolchain.get_cc(), Path::new("riscv64-unknown-elf-gcc"));
+        assert_eq!(toolchain.get_ar(), Path::new("riscv-none-embed-ar"));
+        assert_eq!(
+            toolchain.get_ranlib(),
+            Some(Path::new("riscv-none-embed-ranlib"))
+        );
+        drop(fake);
+
+        // Without any prefixed ar, clang gets the LLVM archiver and GCC the plain one
+        let toolchain = with_env(&[("CC_riscv64gc-unknown-none-elf", "clang")], || {
+            CMakeToolchain::new_without_probe("riscv64gc-unknown-none-elf")
+        });
+        if super::find_program(Path::new("llvm-ar"), &super::path_entries()).is_some() {
+            assert!(toolchain.get_ar().to_str().unwrap().starts_with("llvm-ar"));
+        }
+        let toolchain = with_env(&[], || {
+            CMakeToolchain::new_without_probe("riscv64gc-unknown-none-elf")
+        });
+        assert_eq!(toolchain.get_ar(), Path::new("ar"));
+    }
+
+    #[test]
+    #[cfg(feature = "cargo-config")]
+    fn test_cargo_config() {
+        use std::path::PathBuf;
+
+        let root = env::temp_dir().join("cmake-toolchain-test-cargo-config");
+        let nested = root.join("crates/foo");
+        fs::create_dir_all(root.join(".cargo")).unwrap();
+        fs::create_dir_all(nested.join(".cargo")).unwrap();
+        fs::write(
+            root.join(".cargo/config.toml"),
+            "[target.aarch64-unknown-linux-gnu]\nlinker = \"tools/cc\"\nar = \"aarch64-linux-gnu-ar\"\n",
+        )
+        .unwrap();
+        fs::write(
+            nested.join(".cargo/config.toml"),
+            "[target.aarch64-unknown-linux-gnu]\nlinker = \"clang\"\n",
+        )
+        .unwrap();
+        let find = |key| {
+            super::cargo_config::find_target_setting(&nested, "aarch64-unknown-linux-gnu", key)
+        };
+        assert_eq!(
+            find("linker"),
+            Some((PathBuf::from("clang"), nested.join(".cargo/config.toml")))
+        );
+        assert_eq!(
+            find("ar"),
+            Some((
+                PathBuf::from("aarch64-linux-gnu-ar"),
+                root.join(".cargo/config.toml")
+            ))
+        );
+        fs::remove_file(nested.join(".cargo/config.toml")).unwrap();
+        assert_eq!(
+            find("linker"),
+            Some((root.join("tools/cc"), root.join(".cargo/config.toml")))
+        );
+        assert_eq!(
+            super::cargo_config::find_target_setting(&nested, "x86_64-unknown-linux-gnu", "ar"),
+            None
+        );
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_versioned_gcc() {
+        let fake = FakeToolchain::new(
+            "cmake-toolchain-test-versioned-gcc",
+            &[
+                "powerpc-linux-gnu-gcc-9",
+                "powerpc-linux-gnu-gcc-12",
+                "powerpc-linux-gnu-g++-12",
+                "powerpc-linux-gnu-ar-12",
+            ],
+        );
+        let toolchain = fake.with_path(&[], || {
+            CMakeToolchain::new_without_probe("powerpc-unknown-linux-gnu")
+        });
+        assert_eq!(toolchain.get_cc(), Path::new("powerpc-linux-gnu-gcc-12"));
+        assert_eq!(toolchain.get_cxx(), Path::new("powerpc-linux-gnu-g++-12"));
+        assert_eq!(toolchain.get_ar(), Path::new("powerpc-linux-gnu-ar-12"));
+    }
+
+    #[test]
+    fn test_use_llvm_binutils() {
+        let toolchain = with_env(
+            &[
+                ("CC_aarch64-unknown-linux-gnu", "clang-99"),
+                ("AR_aarch64-unknown-linux-gnu", "/opt/tc/bin/ar"),
+            ],
+            || {
+                let mut toolchain = CMakeToolchain::new("aarch64-unknown-linux-gnu");
+                toolchain.use_llvm_binutils(true);
+                toolchain
+            },
+        );
+        assert_eq!(toolchain.get_ar(), Path::new("/opt/tc/bin/ar"));
+        let ranlib = toolchain.get_ranlib().unwrap().to_str().unwrap();
+        assert!(ranlib.starts_with("llvm-ranlib"), "{}", ranlib);
+        let nm = toolchain.get_nm().unwrap().to_str().unwrap();
+        assert!(nm.starts_with("llvm-nm"), "{}", nm);
+
+        let toolchain = with_env(&[("CC_aarch64-unknown-linux-gnu", "clang")], || {
+            let mut toolchain = CMakeToolchain::new("aarch64-unknown-linux-gnu");
+            toolchain.use_llvm_binutils(false);
+            toolchain
+        });
+        assert!(!toolchain.get_ar().to_str().unwrap().starts_with("llvm-"));
+    }
+
+    #[test]
+    fn test_dotted_target_env() {
+        let toolchain = with_env(
+            &[
+                ("CFLAGS_thumbv8m_main_none_eabi", "-mcpu=cortex-m33"),
+                ("AR_thumbv8m_main_none_eabi", "/opt/tc/bin/arm-none-eabi-ar"),
+            ],
+            || CMakeToolchain::new("thumbv8m.main-none-eabi"),
+        );
+        assert_eq!(toolchain.get_c_flags(), ["-mcpu=cortex-m33"]);
+        assert_eq!(
+            toolchain.get_ar(),
+            Path::new("/opt/tc/bin/arm-none-eabi-ar")
+        );
+        assert_eq!(
+            toolchain.var_names("CC"),
+            [
+                "CC_thumbv8m.main-none-eabi",
+                "CC_thumbv8m.main_none_eabi",
+                "CC_thumbv8m_main_none_eabi",
+                "TARGET_CC",
+                "CC",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_zig() {
+        let toolchain = with_env(&[("CC_aarch64-unknown-linux-gnu", "zig cc")], || {
+            CMakeToolchain::new_without_probe("aarch64-unknown-linux-gnu")
+        });
+        assert!(toolchain.get_use_zig());
+        assert_eq!(toolchain.get_cc(), Path::new("zig"));
+        assert_eq!(toolchain.get_ar(), Path::new("zig"));
+        let content = toolchain.to_cmake_string();
+        assert!(content.contains("set(CMAKE_C_COMPILER_ARG1 cc)\n"));
+        assert!(content.contains("set(CMAKE_C_FLAGS_INIT \"-target aarch64-linux-gnu\")\n"));
+        assert!(content.contains(
+            "set(CMAKE_C_ARCHIVE_CREATE \"<CMAKE_AR> ar qc <TARGET> <LINK_FLAGS> <OBJECTS>\")\n"
+        ));
+        assert!(!content.contains("CMAKE_C_COMPILER_TARGET"));
+
+        let toolchain = with_env(&[], || {
+            let mut toolchain = CMakeToolchain::new("armv7-unknown-linux-gnueabihf");
+            toolchain.use_zig(true);
+            toolchain
+        });
+        assert_eq!(toolchain.get_cxx(), Path::new("zig"));
+        assert!(toolchain
+            .to_cmake_string()
+            .contains("set(CMAKE_CXX_COMPILER_ARG1 c++)\n"));
+    }
+
+    #[test]
+    fn test_zig_target() {
+        for (target, zig) in [
+            ("x86_64-unknown-linux-gnu", "x86_64-linux-gnu"),
+            ("armv7-unknown-linux-gnueabihf", "arm-linux-gnueabihf"),
+            ("i686-pc-windows-gnu", "x86-windows-gnu"),
+            ("aarch64-apple-darwin", "aarch64-macos"),
+            ("aarch64-apple-ios-sim", "aarch64-ios-simulator"),
+            ("aarch64-linux-android", "aarch64-linux-android"),
+            ("riscv64gc-unknown-linux-musl", "riscv64-linux-musl"),
+            ("thumbv7em-none-eabihf", "thumb-freestanding-eabihf"),
+            ("wasm32-wasip1", "wasm32-wasi"),
+        ] {
+            assert_eq!(super::zig_target(target), zig, "{}", target);
+        }
+    }
+
+    #[test]
+    fn test_explain() {
+        let toolchain = with_env(&[("AR_x86_64-unknown-linux-gnu", "/opt/tc/bin/ar")], || {
+            CMakeToolchain::new("x86_64-unknown-linux-gnu")
+        });
+        let report = toolchain.explain();
+        let ar = report.tools.iter().find(|tool| tool.name == "ar").unwrap();
+        assert_eq!(ar.path.as_deref(), Some(Path::new("/opt/tc/bin/ar")));
+        assert_eq!(
+            ar.source,
+            Some(ToolSource::Env("AR_x86_64-unknown-linux-gnu".to_string()))
+        );
+        let report = report.to_string();
+        assert!(report.starts_with("CMake toolchain for target x86_64-unknown-linux-gnu on host "));
+        assert!(report.contains(
+            "  ar       /opt/tc/bin/ar (environment variable AR_x86_64-unknown-linux-gnu)\n"
+        ));
+    }
+
+    #[test]
+    fn test_validate() {
+        let meta = rustc_version::version_meta().unwrap();
+        let mut toolchain = with_env(&[], || CMakeToolchain::new(&meta.host));
+        toolchain.ar("/nonexistent/bin/aarch64-linux-gnu-ar".into());
+        let err = toolchain.validate().unwrap_err();
+        assert!(matches!(err, crate::Error::ToolNotFound { ref tool, .. } if tool == "ar"));
+    }
+
+    #[test]
+    fn test_build() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Resolved>();
+
+        let meta = rustc_version::version_meta().unwrap();
+        let toolchain = with_env(&[], || CMakeToolchain::new(&meta.host));
+        let resolved = toolchain.clone().build().unwrap();
+        assert_eq!(resolved.get_cc(), toolchain.get_cc());
+        assert_eq!(
+            resolved.clone().to_cmake_string(),
+            toolchain.to_cmake_string()
+        );
+
+        let mut toolchain = toolchain;
+        toolchain.cc("/nonexistent/bin/gcc".into());
+        let err = toolchain.build().unwrap_err();
+        assert!(matches!(err, crate::Error::ToolNotFound { ref tool, .. } if tool == "cc"));
+    }
+
+    #[test]
+    fn test_llvm_triple() {
+        let mut toolchain = with_env(&[], || {
+            CMakeToolchain::new_without_probe("x86_64-unknown-linux-gnu")
+        });
+        for (target, triple) in [
+            ("x86_64-unknown-linux-gnu", "x86_64-unknown-linux-gnu"),
+            (
+                "armv7-unknown-linux-gnueabihf",
+                "armv7-unknown-linux-gnueabihf",
+            ),
+            (
+                "thumbv7neon-unknown-linux-gnueabihf",
+                "armv7a-unknown-linux-gnueabihf",
+            ),
+            (
+                "thumbv7neon-linux-androideabi",
+                "armv7a-linux-androideabi21",
+            ),
+            ("aarch64-linux-android", "aarch64-linux-android21"),
+            ("riscv64gc-unknown-linux-gnu", "riscv64-unknown-linux-gnu"),
+            ("riscv32imac-unknown-none-elf", "riscv32-unknown-none-elf"),
+            ("x86_64-pc-windows-msvc", "x86_64-pc-windows-msvc"),
+            ("aarch64-pc-windows-gnullvm", "aarch64-pc-windows-gnu"),
+            ("aarch64-apple-darwin", "arm64-apple-macosx"),
+            ("aarch64-apple-ios", "arm64-apple-ios"),
+            ("aarch64-apple-ios-sim", "arm64-apple-ios-simulator"),
+            ("x86_64-apple-ios", "x86_64-apple-ios-simulator"),
+            ("aarch64-apple-ios-macabi", "arm64-apple-ios-macabi"),
+            ("thumbv8m.main-none-eabihf", "thumbv8m.main-none-eabihf"),
+        ] {
+            toolchain.target = target.to_string();
+            assert_eq!(toolchain.llvm_triple(), triple, "{}", target);
+        }
+    }
+
+    #[test]
+    fn test_gnullvm() {
+        let toolchain = with_env(&[], || {
+            CMakeToolchain::new_without_probe("aarch64-pc-windows-gnullvm")
+        });
+        assert_eq!(toolchain.get_system_name(), "Windows");
+        assert_eq!(toolchain.get_cc(), Path::new("aarch64-w64-mingw32-clang"));
+        assert_eq!(
+            toolchain.get_cxx(),
+            Path::new("aarch64-w64-mingw32-clang++")
+        );
+        assert_eq!(toolchain.get_compiler_family(), CompilerFamily::Clang);
+        assert_eq!(
+            toolchain.get_compiler_target(),
+            Some("aarch64-pc-windows-gnu")
+        );
+        assert!(toolchain.get_ar().to_string_lossy().starts_with("llvm-ar"));
+        assert!(toolchain
+            .get_ranlib()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("llvm-ranlib"));
+        assert!(toolchain
+            .to_cmake_string()
+            .contains("set(CMAKE_SYSTEM_NAME Windows)\n"));
+    }
+
+    #[test]
+    fn test_system_processor() {
+        let meta = rustc_version::version_meta().unwrap();
+        let mut toolchain = with_env(&[], || CMakeToolchain::new(&meta.host));
+        for (target, processor) in [
+            ("x86_64-unknown-linux-gnu", "x86_64"),
+            ("aarch64-unknown-linux-gnu", "aarch64"),
+            ("armv7-unknown-linux-gnueabihf", "arm"),
+            ("thumbv7em-none-eabihf", "arm"),
+            ("i686-pc-windows-msvc", "i686"),
+            ("riscv64gc-unknown-linux-gnu", "riscv64"),
+            ("powerpc64le-unknown-linux-gnu", "ppc64le"),
+            ("mips-unknown-linux-gnu", "mips"),
+            ("mipsel-unknown-linux-musl", "mipsel"),
+            ("mips64el-unknown-linux-gnuabi64", "mips64el"),
+            ("mipsisa32r6el-unknown-linux-gnu", "mipsel"),
+            ("mipsisa64r6-unknown-linux-gnuabi64", "mips64"),
+            ("x86_64-unknown-freebsd", "amd64"),
+            ("x86_64-unknown-openbsd", "amd64"),
+            ("x86_64-unknown-netbsd", "x86_64"),
+            ("i686-unknown-freebsd", "i386"),
+            ("aarch64-unknown-freebsd", "aarch64"),
+        ] {
+            toolchain.target = target.to_string();
+            assert_eq!(toolchain.find_system_processor(), processor, "{}", target);
+        }
+    }
+
+    #[test]
+    fn test_cortex_m() {
+        let mut toolchain = with_env(&[], || {
+            CMakeToolchain::new_without_probe("thumbv7em-none-eabihf")
+        });
+        assert_eq!(toolchain.get_system_processor(), "cortex-m4");
+        assert_eq!(toolchain.get_fpu(), Some("fpv4-sp-d16"));
+        let content = toolchain.to_cmake_string();
+        let flags = "-mthumb -mcpu=cortex-m4 -mfpu=fpv4-sp-d16 -mfloat-abi=hard";
+        for lang in ["C", "CXX", "ASM"] {
+            assert!(
+                content.contains(&format!("set(CMAKE_{}_FLAGS_INIT \"{}", lang, flags)),
+                "{}",
+                content
+            );
+        }
+
+        toolchain.cpu("cortex-m7".to_string());
+        assert_eq!(toolchain.get_system_processor(), "cortex-m7");
+        assert!(toolchain.to_cmake_string().contains("-mcpu=cortex-m7"));
+
+        with_env(&[], || {
+            toolchain.target("armv7-unknown-linux-gnueabihf".to_string());
+        });
+        assert_eq!(toolchain.get_cpu(), None);
+        assert_eq!(toolchain.get_system_processor(), "arm");
+        assert!(!toolchain.to_cmake_string().contains("CMAKE_ASM_FLAGS_INIT"));
+    }
+
+    #[test]
+    fn test_suffixes() {
+        let mut toolchain = with_env(&[], || CMakeToolchain::new_without_probe("wasm32-wasip1"));
+        assert_eq!(toolchain.get_executable_suffix(), Some(".wasm"));
+        toolchain.static_library_suffix(".lib".to_string());
+        let content = toolchain.to_cmake_string();
+        assert!(content.contains("set(CMAKE_EXECUTABLE_SUFFIX \".wasm\")\n"));
+        assert!(content.contains("set(CMAKE_STATIC_LIBRARY_SUFFIX \".lib\")\n"));
+        assert!(!content.contains("CMAKE_SHARED_LIBRARY_SUFFIX"));
+
+        with_env(&[], || {
+            toolchain.target("thumbv7em-none-eabihf".to_string());
+        });
+        assert_eq!(toolchain.get_executable_suffix(), Some(".elf"));
+        with_env(&[], || {
+            toolchain.target("wasm32-unknown-emscripten".to_string());
+        });
+        assert_eq!(toolchain.get_executable_suffix(), None);
+        with_env(&[], || {
+            toolchain.target("x86_64-unknown-illumos".to_string());
+        });
+        assert_eq!(toolchain.get_executable_suffix(), None);
+        with_env(&[], || {
+            toolchain.target("aarch64-unknown-linux-gnu".to_string());
+        });
+        assert!(!toolchain
+            .to_cmake_string()
+            .contains("CMAKE_EXECUTABLE_SUFFIX"));
+    }
+
+    #[test]
+    fn test_bare_wasm() {
+        let toolchain = with_env(&[], || {
+            CMakeToolchain::new_without_probe("wasm32-unknown-unknown")
+        });
+        assert_eq!(toolchain.get_system_name(), "Generic");
+        assert_eq!(toolchain.get_cc(), Path::new("clang"));
+        assert_eq!(toolchain.get_cxx(), Path::new("clang++"));
+        assert_eq!(toolchain.get_compiler_family(), CompilerFamily::Clang);
+        assert_eq!(
+            toolchain.get_compiler_target(),
+            Some("wasm32-unknown-unknown")
+        );
+        assert!(toolchain.get_ar().to_str().unwrap().starts_with("llvm-ar"));
+        assert!(toolchain
+            .get_ranlib()
+            .and_then(Path::to_str)
+            .unwrap()
+            .starts_with("llvm-ranlib"));
+        assert_eq!(toolchain.get_sysroot(), None);
+        let content = toolchain.to_cmake_string();
+        assert!(content.contains("set(CMAKE_TRY_COMPILE_TARGET_TYPE STATIC_LIBRARY)\n"));
+        assert!(content.contains("set(CMAKE_C_COMPILER_TARGET wasm32-unknown-unknown)\n"));
+    }
+
+    #[test]
+    fn test_recommended() {
+        let host = rustc_version::version_meta().unwrap().host;
+        let toolchain = with_env(&[], || CMakeToolchain::recommended(&host).unwrap());
+        assert!(!toolchain.get_auto_emulator());
+        if !host.contains("windows") {
+            assert_eq!(toolchain.get_position_independent_code(), Some(true));
+        }
+
+        let toolchain = with_env(&[], || {
+            CMakeToolchain::recommended("thumbv7em-none-eabihf").unwrap()
+        });
+        // qemu user mode emulation needs an operating system
+        assert!(!toolchain.get_auto_emulator());
+        assert_eq!(toolchain.get_position_independent_code(), None);
+        assert_eq!(
+            toolchain.get_try_compile_target_type(),
+            Some(TryCompileTargetType::StaticLibrary)
+        );
+
+        if host != "riscv64gc-unknown-linux-gnu" {
+            let toolchain = with_env(&[], || {
+                CMakeToolchain::recommended("riscv64gc-unknown-linux-gnu").unwrap()
+            });
+            assert!(toolchain.get_auto_emulator());
+        }
+    }
+
+    #[test]
+    fn test_sysroot_with_spaces() {
+        let sysroot = "C:/Users/Jöhn Doe/sdk";
+        let mut toolchain = with_env(&[], || {
+            CMakeToolchain::new_without_probe("aarch64-unknown-linux-gnu")
+        });
+        toolchain
+            .sysroot(sysroot.into())
+            .add_find_root_path("/opt/aarch64 libs".into())
+            .sysroot_as_flag(true);
+        let content = toolchain.to_cmake_string();
+        assert!(content.contains("set(CMAKE_SYSROOT \"C:/Users/Jöhn Doe/sdk\")\n"));
+        assert!(content.contains(
+            "set(CMAKE_FIND_ROOT_PATH \"C:/Users/Jöhn Doe/sdk\" \"/opt/aarch64 libs\")\n"
+        ));
+        assert!(content.contains(
+            "set(CMAKE_EXE_LINKER_FLAGS_INIT \"\\\"--sysroot=C:/Users/Jöhn Doe/sdk\\\"\")\n"
+        ));
+        let defines = toolchain.as_cmake_defines();
+        assert!(defines.contains(&(
+            "CMAKE_EXE_LINKER_FLAGS_INIT".to_string(),
+            "\"--sysroot=C:/Users/Jöhn Doe/sdk\"".to_string()
+        )));
+        #[cfg(feature = "meson")]
+        assert!(toolchain
+            .to_meson_cross_file()
+            .contains("sys_root = 'C:/Users/Jöhn Doe/sdk'\n"));
+    }
+
+    #[test]
+    fn test_ohos() {
+        let toolchain = with_env(&[], || {
+            CMakeToolchain::new_without_probe("aarch64-unknown-linux-ohos")
+        });
+        assert_eq!(toolchain.get_system_name(), "Linux");
+        assert_eq!(toolchain.get_cc(), Path::new("clang"));
+        assert_eq!(toolchain.get_compiler_target(), Some("aarch64-linux-ohos"));
+
+        let vars = [("OHOS_NDK_HOME", "/opt/ohos-sdk/linux")];
+        let toolchain = with_env(&vars, || {
+            CMakeToolchain::new_without_probe("armv7-unknown-linux-ohos")
+        });
+        let bin = Path::new("/opt/ohos-sdk/linux/native/llvm/bin");
+        assert_eq!(toolchain.get_system_name(), "OHOS");
+        assert_eq!(toolchain.get_cc(), bin.join("clang"));
+        assert_eq!(toolchain.get_cxx(), bin.join("clang++"));
+        assert_eq!(toolchain.get_ar(), bin.join("llvm-ar"));
+        assert_eq!(
+            toolchain.get_ranlib(),
+            Some(bin.join("llvm-ranlib").as_path())
+        );
+        assert_eq!(toolchain.get_compiler_target(), Some("arm-linux-ohos"));
+        assert_eq!(
+            toolchain.get_sysroot(),
+            Some(Path::new("/opt/ohos-sdk/linux/native/sysroot"))
+        );
+        assert!(toolchain
+            .to_cmake_string()
+            .contains("set(CMAKE_SYSTEM_NAME OHOS)\n"));
+    }
+
+    #[test]
+    fn test_mips_flags() {
+        let mut toolchain = with_env(&[], || {
+            CMakeToolchain::new_without_probe("mipsel-unknown-linux-musl")
+        });
+        assert_eq!(toolchain.get_system_processor(), "mipsel");
+        assert_eq!(toolchain.get_float_abi(), Some("soft"));
+        assert_eq!(toolchain.arch_flags(), ["-msoft-float"]);
+        toolchain.float_abi("hard".to_string());
+        assert_eq!(toolchain.arch_flags(), ["-mhard-float"]);
+
+        with_env(&[], || {
+            toolchain.target("mips-unknown-linux-gnu".to_string());
+        });
+        assert_eq!(toolchain.get_float_abi(), None);
+        assert!(toolchain.arch_flags().is_empty());
+        with_env(&[], || {
+            toolchain.target("mips64el-unknown-linux-gnuabi64".to_string());
+        });
+        assert_eq!(toolchain.arch_flags(), ["-mabi=64"]);
+        with_env(&[], || {
+            toolchain.target("mipsisa64r6el-unknown-linux-gnuabi64".to_string());
+        });
+        assert_eq!(toolchain.arch_flags(), ["-march=mips64r6", "-mabi=64"]);
+        toolchain.cpu("octeon".to_string());
+        assert_eq!(toolchain.arch_flags(), ["-march=octeon", "-mabi=64"]);
+        assert_eq!(toolchain.get_system_processor(), "mips64el");
+    }
+
+    #[test]
+    fn test_system_name() {
+        let meta = rustc_version::version_meta().unwrap();
+        let mut toolchain = with_env(&[], || CMakeToolchain::new(&meta.host));
+        for (target, system_name) in [
+            ("x86_64-unknown-linux-gnu", "Linux"),
+            ("aarch64-linux-android", "Linux"),
+            ("x86_64-pc-windows-msvc", "Windows"),
+            ("x86_64-unknown-freebsd", "FreeBSD"),
+            ("x86_64-unknown-openbsd", "OpenBSD"),
+            ("aarch64-unknown-netbsd", "NetBSD"),
+            ("x86_64-unknown-haiku", "Haiku"),
+            ("x86_64-unknown-illumos", "SunOS"),
+            ("sparcv9-sun-solaris", "SunOS"),
+            ("x86_64-unknown-dragonfly", "DragonFly"),
+            ("x86_64-unknown-fuchsia", "Fuchsia"),
+            ("x86_64-unknown-redox", "Redox"),
+            ("powerpc64-ibm-aix", "AIX"),
+            ("x86_64-unknown-uefi", "UEFI"),
+            ("x86_64-unknown-hermit", "Hermit"),
+            ("thumbv7em-none-eabihf", "Generic"),
+            ("riscv64gc-unknown-none-elf", "Generic"),
+            ("wasm32-unknown-unknown", "Generic"),
+        ] {
+            toolchain.target = target.to_string();
+            assert_eq!(toolchain.find_system_name(), system_name, "{}", target);
+        }
+    }
+
+    #[test]
+    fn test_endianness() {
+        let meta = rustc_version::version_meta().unwrap();
+        let mut toolchain = with_env(&[], || CMakeToolchain::new(&meta.host));
+        for (target, endianness) in [
+            ("x86_64-unknown-linux-gnu", Endianness::Little),
+            ("aarch64-unknown-linux-gnu", Endianness::Little),
+            ("powerpc-unknown-linux-gnu", Endianness::Big),
+            ("powerpc64-unknown-linux-gnu", Endianness::Big),
+            ("powerpc64le-unknown-linux-gnu", Endianness::Little),
+            ("mips-unknown-linux-gnu", Endianness::Big),
+            ("mipsel-unknown-linux-gnu", Endianness::Little),
+            ("mips64-unknown-linux-gnuabi64", Endianness::Big),
+            ("s390x-unknown-linux-gnu", Endianness::Big),
+            ("sparc64-unknown-linux-gnu", Endianness::Big),
+            ("armebv7r-none-eabi", Endianness::Big),
+        ] {
+            toolchain.target = target.to_string();
+            assert_eq!(toolchain.endianness(), endianness, "{}", target);
+        }
+    }
+
+    #[test]
+    fn test_android_api_level_without_ndk() {
+        let vars = [
+            ("ANDROID_NDK_HOME", "/nonexistent/ndk"),
+            ("NDK_HOME", "/nonexistent/ndk"),
+        ];
+        let mut toolchain = with_env(&vars, || {
+            CMakeToolchain::new_without_probe("aarch64-linux-android")
+        });
+        assert_eq!(
+            toolchain.get_cc(),
+            Path::new("aarch64-linux-android21-clang")
+        );
+        assert_eq!(
+            toolchain.get_compiler_target(),
+            Some("aarch64-linux-android21")
+        );
+        with_env(&vars, || {
+            toolchain.android_api_level(29);
+        });
+        assert_eq!(
+            toolchain.get_cc(),
+            Path::new("aarch64-linux-android29-clang")
+        );
+        assert_eq!(
+            toolchain.get_cxx(),
+            Path::new("aarch64-linux-android29-clang++")
+        );
+        assert_eq!(
+            toolchain.get_compiler_target(),
+            Some("aarch64-linux-android29")
+        );
+        assert_eq!(toolchain.get_system_version(), Some("29"));
+    }
+
+    #[test]
+    fn test_android_ndk_prefixes() {
+        for (target, clang, binutils) in [
+            (
+                "aarch64-linux-android",
+                "aarch64-linux-android",
+                "aarch64-linux-android",
+            ),
+            (
+                "arm-linux-androideabi",
+                "armv7a-linux-androideabi",
+                "arm-linux-androideabi",
+            ),
+            (
+                "armv7-linux-androideabi",
+                "armv7a-linux-androideabi",
+                "arm-linux-androideabi",
+            ),
+            (
+                "thumbv7neon-linux-androideabi",
+                "armv7a-linux-androideabi",
+                "arm-linux-androideabi",
+            ),
+            (
+                "i686-linux-android",
+                "i686-linux-android",
+                "i686-linux-android",
+            ),
+            (
+                "x86_64-linux-android",
+                "x86_64-linux-android",
+                "x86_64-linux-android",
+            ),
+        ] {
+            assert_eq!(
+                super::android_ndk_prefixes(target),
+                (clang, binutils),
+                "{}",
+                target
+            );
+        }
+    }
+
+    #[test]
+    fn test_apple_targets() {
+        for (target, sdk, arch) in [
+            ("x86_64-apple-darwin", "macosx", "x86_64"),
+            ("aarch64-apple-darwin", "macosx", "arm64"),
+            ("aarch64-apple-ios", "iphoneos", "arm64"),
+            ("aarch64-apple-ios-sim", "iphonesimulator", "arm64"),
+            ("x86_64-apple-ios", "iphonesimulator", "x86_64"),
+            ("aarch64-apple-ios-macabi", "macosx", "arm64"),
+        ] {
+            assert_eq!(super::apple_sdk_name(target), Some(sdk), "{}", target);
+            assert_eq!(super::apple_arch(target), arch, "{}", target);
+        }
+        assert_eq!(super::apple_sdk_name("x86_64-unknown-linux-gnu"), None);
+
+        let sdk_root = "/Applications/Xcode.app/Contents/Developer/Platforms/iPhoneOS.platform/Developer/SDKs/iPhoneOS17.0.sdk";
+        let toolchain = with_env(&[("SDKROOT", sdk_root)], || {
+            CMakeToolchain::new_without_probe("aarch64-apple-ios")
+        });
+        assert_eq!(toolchain.osx_sysroot.as_deref(), Some(Path::new(sdk_root)));
+        let toolchain = with_env(&[("SDKROOT", sdk_root)], || {
+            CMakeToolchain::new_without_probe("aarch64-apple-darwin")
+        });
+        assert_ne!(toolchain.osx_sysroot.as_deref(), Some(Path::new(sdk_root)));
+    }
+
+    #[test]
+    #[cfg(feature = "meson")]
+    fn test_to_meson_cross_file() {
+        let toolchain = with_env(
+            &[
+                (
+                    "CC_aarch64-unknown-linux-gnu",
+                    "ccache aarch64-linux-gnu-gcc",
+                ),
+                ("AR_aarch64-unknown-linux-gnu", "/opt/tc/bin/ar"),
+            ],
+            || CMakeToolchain::new("aarch64-unknown-linux-gnu"),
+        );
+        let content = toolchain.to_meson_cross_file();
+        assert!(content.contains("c = ['ccache', 'aarch64-linux-gnu-gcc']\n"));
+        assert!(content.contains("ar = '/opt/tc/bin/ar'\n"));
+        assert!(content.ends_with(
+            "[host_machine]\nsystem = 'linux'\ncpu_family = 'aarch64'\ncpu = 'aarch64'\nendian = 'little'\n"
+        ));
+    }
+
+    #[test]
+    fn test_write_to_file_concurrently() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::thread;
+
+        let dir = temp_dir("cmake-toolchain-test-concurrent-write");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("toolchain.cmake");
+        let toolchain = with_env(&[], || {
+            CMakeToolchain::new_without_probe("aarch64-unknown-linux-gnu")
+        });
+        let expected = toolchain.to_cmake_string();
+        let done = AtomicBool::new(false);
+        thread::scope(|scope| {
+            let writers: Vec<_> = (0..8)
+                .map(|_| {
+                    scope.spawn(|| {
+                        for _ in 0..50 {
+                            toolchain.write_to_file(&path).unwrap();
+                        }
+                    })
+                })
+                .collect();
+            scope.spawn(|| {
+                while !done.load(Ordering::Relaxed) {
+                    if let Ok(content) = fs::read_to_string(&path) {
+                        assert_eq!(content, expected);
+                    }
+                }
+            });
+            for writer in writers {
+                writer.join().unwrap();
+            }
+            done.store(true, Ordering::Relaxed);
+        });
+        assert_eq!(fs::read_to_string(&path).unwrap(), expected);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_errors() {
+        let toolchain = with_env(&[], || {
+            CMakeToolchain::new_without_probe("aarch64-unknown-linux-gnu")
+        });
+        let path = temp_dir("cmake-toolchain-test-write-errors").join("toolchain.cmake");
+        match toolchain.write_to_file(&path) {
+            Err(Error::Io {
+                target,
+                path: err_path,
+                ..
+            }) => {
+                assert_eq!(target, "aarch64-unknown-linux-gnu");
+                assert_eq!(err_path, path);
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+        assert!(matches!(
+            with_env(&[], || toolchain.emit_to_out_dir()),
+            Err(Error::OutDirNotSet { .. })
+        ));
+    }
+
+    #[test]
+    fn test_emulator() {
+        let mut toolchain = with_env(&[], || {
+            CMakeToolchain::new_without_probe("aarch64-unknown-linux-gnu")
+        });
+        toolchain.auto_emulator(true);
+        assert!(toolchain.get_emulator().is_empty());
+        toolchain.sysroot("/usr/aarch64-linux-gnu".into());
+        assert_eq!(
+            toolchain.get_emulator(),
+            ["qemu-aarch64", "-L", "/usr/aarch64-linux-gnu"]
+        );
+        assert!(toolchain.to_cmake_string().contains(
+            "set(CMAKE_CROSSCOMPILING_EMULATOR \"qemu-aarch64;-L;/usr/aarch64-linux-gnu\")\n"
+        ));
+        with_env(&[], || {
+            toolchain.target("armv7-unknown-linux-gnueabihf".to_string());
+        });
+        assert_eq!(toolchain.get_emulator()[0], "qemu-arm");
+
+        toolchain.emulator(vec!["/opt/qemu/bin/qemu-arm".to_string()]);
+        assert_eq!(toolchain.get_emulator(), ["/opt/qemu/bin/qemu-arm"]);
+        toolchain.auto_emulator(false).emulator(Vec::new());
+        assert!(!toolchain
+            .to_cmake_string()
+            .contains("CMAKE_CROSSCOMPILING_EMULATOR"));
+    }
+
+    #[test]
+    fn test_rpath() {
+        let mut toolchain = with_env(&[], || {
+            CMakeToolchain::new_without_probe("aarch64-unknown-linux-gnu")
+        });
+        assert!(toolchain.get_skip_rpath());
+        let content = toolchain.to_cmake_string();
+        assert!(content.contains("set(CMAKE_SKIP_BUILD_RPATH ON)\n"));
+        assert!(content.contains("set(CMAKE_SKIP_RPATH ON)\n"));
+
+        toolchain.install_rpath("$ORIGIN/../lib".to_string());
+        let content = toolchain.to_cmake_string();
+        assert!(content.contains("set(CMAKE_SKIP_BUILD_RPATH ON)\n"));
+        assert!(!content.contains("CMAKE_SKIP_RPATH"));
+        assert!(content.contains("set(CMAKE_INSTALL_RPATH \"\\$ORIGIN/../lib\")\n"));
+
+        toolchain.skip_rpath(false);
+        assert!(!toolchain.to_cmake_string().contains("SKIP_BUILD_RPATH"));
+
+        let meta = rustc_version::version_meta().unwrap();
+        let toolchain = with_env(&[], || CMakeToolchain::new_without_probe(&meta.host));
+        assert!(!toolchain.get_skip_rpath());
+        assert!(!toolchain.to_cmake_string().contains("RPATH"));
+    }
+
+    #[test]
+    fn test_assume_compiler_id() {
+        let mut toolchain = with_env(&[], || {
+            CMakeToolchain::new_without_probe("thumbv7em-none-eabihf")
+        });
+        let content = toolchain.to_cmake_string();
+        assert!(!content.contains("COMPILER_FORCED"));
+        assert!(!content.contains("BYTE_ORDER"));
+        toolchain.assume_compiler_id(true);
+        let content = toolchain.to_cmake_string();
+        assert!(content.contains("set(CMAKE_C_COMPILER_ID GNU)\n"));
+        assert!(content.contains("set(CMAKE_C_COMPILER_FORCED TRUE)\n"));
+        assert!(content.contains("set(CMAKE_CXX_COMPILER_ID GNU)\n"));
+        assert!(content.contains("set(CMAKE_CXX_COMPILER_FORCED TRUE)\n"));
+        assert!(content.contains("set(CMAKE_C_BYTE_ORDER LITTLE_ENDIAN)\n"));
+
+        let mut toolchain = with_env(&[("CC_thumbv7em-none-eabihf", "clang")], || {
+            CMakeToolchain::new_without_probe("thumbv7em-none-eabihf")
+        });
+        toolchain.assume_compiler_id(true);
+        assert!(toolchain
+            .to_cmake_string()
+            .contains("set(CMAKE_C_COMPILER_ID Clang)\n"));
+    }
+
+    #[test]
+    fn test_compiler_family() {
+        let mut toolchain = with_env(&[], || {
+            CMakeToolchain::new_without_probe("aarch64-unknown-linux-gnu")
+        });
+        assert_eq!(toolchain.get_compiler_family(), CompilerFamily::Gnu);
+        toolchain
+            .assume_compiler_id(true)
+            .cc("/usr/bin/clang".into());
+        assert_eq!(toolchain.get_compiler_family(), CompilerFamily::Clang);
+        assert_eq!(
+            toolchain.get_compiler_target(),
+            Some("aarch64-unknown-linux-gnu")
+        );
+        let content = toolchain.to_cmake_string();
+        assert!(content.contains("set(CMAKE_C_COMPILER_ID Clang)\n"));
+        assert!(content.contains("set(CMAKE_C_COMPILER_TARGET aarch64-unknown-linux-gnu)\n"));
+
+        toolchain.cc_command("ccache aarch64-linux-gnu-gcc");
+        assert_eq!(toolchain.get_compiler_family(), CompilerFamily::Gnu);
+        assert_eq!(toolchain.get_compiler_target(), None);
+
+        // An explicit family is kept when the C compiler changes
+        toolchain.compiler_family(CompilerFamily::Clang);
+        assert_eq!(
+            toolchain.get_compiler_target(),
+            Some("aarch64-unknown-linux-gnu")
+        );
+        toolchain.cc("cc".into());
+        assert_eq!(toolchain.get_compiler_family(), CompilerFamily::Clang);
+        toolchain.compiler_family(CompilerFamily::Gnu);
+        assert_eq!(toolchain.get_compiler_target(), None);
+        assert!(!toolchain
+            .to_cmake_string()
+            .contains("CMAKE_C_COMPILER_TARGET"));
+    }
+
+    #[test]
+    fn test_archive_rules() {
+        let mut toolchain = with_env(&[], || {
+            CMakeToolchain::new_without_probe("aarch64-unknown-linux-gnu")
+        });
+        assert!(!toolchain.to_cmake_string().contains("ARCHIVE_CREATE"));
+        toolchain
+            .archive_create("<CMAKE_AR> crs <TARGET> <OBJECTS>".to_string())
+            .archive_finish("true".to_string());
+        let content = toolchain.to_cmake_string();
+        for lang in ["C", "CXX", "ASM"] {
+            assert!(content.contains(&format!(
+                "set(CMAKE_{}_ARCHIVE_CREATE \"<CMAKE_AR> crs <TARGET> <OBJECTS>\")\n",
+                lang
+            )));
+            assert!(content.contains(&format!("set(CMAKE_{}_ARCHIVE_FINISH \"true\")\n", lang)));
+        }
+        assert!(!content.contains("ARCHIVE_APPEND"));
+
+        let mut toolchain = with_env(&[], || {
+            CMakeToolchain::new_without_probe("aarch64-apple-darwin")
+        });
+        toolchain.ar("/usr/bin/llvm-ar".into());
+        let content = toolchain.to_cmake_string();
+        assert!(content.contains(
+            "set(CMAKE_C_ARCHIVE_CREATE \"<CMAKE_AR> --format=darwin qc <TARGET> <LINK_FLAGS> <OBJECTS>\")\n"
+        ));
+        assert!(!content.contains("ARCHIVE_FINISH"));
+        toolchain.emit_archiver(false);
+        assert!(!toolchain.to_cmake_string().contains("ARCHIVE_CREATE"));
+
+        let mut toolchain = with_env(&[], || {
+            CMakeToolchain::new_without_probe("x86_64-unknown-illumos")
+        });
+        toolchain.ar("gar".into());
+        assert_eq!(
+            toolchain.archive_rules()[0].as_deref(),
+            Some("<CMAKE_AR> qcs <TARGET> <LINK_FLAGS> <OBJECTS>")
+        );
+    }
+
+    #[test]
+    fn test_emit_archiver() {
+        let mut toolchain = with_env(&[], || {
+            CMakeToolchain::new_without_probe("aarch64-unknown-linux-gnu")
+        });
+        toolchain
+            .ar("/opt/tc/bin/aarch64-linux-gnu-ar".into())
+            .ranlib("/opt/tc/bin/aarch64-linux-gnu-ranlib".into());
+        assert!(toolchain.get_emit_archiver());
+        let content = toolchain.to_cmake_string();
+        assert!(content.contains("set(CMAKE_AR "));
+        assert!(content.contains("set(CMAKE_RANLIB "));
+
+        toolchain.emit_archiver(false);
+        let content = toolchain.to_cmake_string();
+        assert!(!content.contains("CMAKE_AR"));
+        assert!(!content.contains("CMAKE_RANLIB"));
+        assert!(content.contains("set(CMAKE_C_COMPILER "));
+    }
+
+    #[test]
+    fn test_as_cmake_defines() {
+        let mut toolchain = with_env(&[], || {
+            CMakeToolchain::new_without_probe("aarch64-unknown-linux-gnu")
+        });
+        toolchain
+            .sysroot("/opt/sysroot".into())
+            .add_find_root_path("/opt/extra".into())
+            .add_c_flag("-O2")
+            .add_c_flag("-DNAME=\"value\"")
+            .define("CUSTOM_VAR", "some value");
+        let defines = toolchain.as_cmake_defines();
+        let get = |name: &str| {
+            defines
+                .iter()
+                .find(|(var, _)| var == name)
+                .map(|(_, value)| value.as_str())
+        };
+        assert_eq!(get("CMAKE_SYSTEM_NAME"), Some("Linux"));
+        assert_eq!(get("CMAKE_C_COMPILER"), Some("aarch64-linux-gnu-gcc"));
+        assert_eq!(get("CMAKE_C_FLAGS_INIT"), Some("-O2 -DNAME=\"value\""));
+        assert_eq!(get("CMAKE_FIND_ROOT_PATH"), Some("/opt/sysroot;/opt/extra"));
+        assert_eq!(get("CUSTOM_VAR"), Some("some value"));
+
+        // Every `set()` of the toolchain file has a define
+        let content = toolchain.to_cmake_string();
+        let set_lines = content
+            .lines()
+            .filter(|line| line.starts_with("set("))
+            .count();
+        assert_eq!(set_lines, defines.len());
+        for (var, _) in &defines {
+            assert!(content.contains(&format!("set({} ", var)), "{}", var);
+        }
+    }
+
+    #[test]
+    fn test_env_vars() {
+        let cc = "/opt/tc/bin/aarch64-linux-gnu-gcc -mcpu=cortex-a72";
+        let mut toolchain = with_env(&[("CC_aarch64-unknown-linux-gnu", cc)], || {
+            CMakeToolchain::new_without_probe("aarch64-unknown-linux-gnu")
+        });
+        toolchain
+            .ar("/opt/tc/bin/aarch64-linux-gnu-ar".into())
+            .ranlib("/opt/tc/bin/aarch64-linux-gnu-ranlib".into());
+        let vars = toolchain.env_vars();
+        let get = |name: &str| {
+            vars.iter()
+                .find(|(var, _)| var == name)
+                .map(|(_, value)| value.as_str())
+        };
+        assert_eq!(get("CC"), Some(cc));
+        assert_eq!(get("CC_aarch64_unknown_linux_gnu"), Some(cc));
+        assert_eq!(get("AR"), Some("/opt/tc/bin/aarch64-linux-gnu-ar"));
+        assert_eq!(
+            get("RANLIB_aarch64_unknown_linux_gnu"),
+            Some("/opt/tc/bin/aarch64-linux-gnu-ranlib")
+        );
+        assert!(get("CXX").is_some());
+
+        toolchain.cc("zig".into());
+        assert_eq!(
+            toolchain.env_vars()[0].1,
+            "zig cc -target aarch64-linux-gnu"
+        );
+
+        toolchain
+            .cc_command("ccache clang")
+            .cxx("C:/Program Files/LLVM/bin/clang++".into());
+        let vars = toolchain.env_vars();
+        assert_eq!(vars[0], ("CC".to_string(), "ccache clang".to_string()));
+        assert_eq!(
+            vars[1],
+            (
+                "CXX".to_string(),
+                "ccache \"C:/Program Files/LLVM/bin/clang++\"".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_flags_file() {
+        let dir = temp_dir("cmake-toolchain-test-flags-file");
+        fs::create_dir_all(&dir).unwrap();
+        let flags_file = dir.join("flags.cmake");
+        let mut toolchain = with_env(&[], || {
+            CMakeToolchain::new_without_probe("aarch64-unknown-linux-gnu")
+        });
+        toolchain
+            .flags_file(flags_file.clone())
+            .add_c_flag("-O2")
+            .add_c_flag("-DNAME=\"some value\"")
+            .add_exe_linker_flag("-Wl,--gc-sections");
+        let content = toolchain.to_cmake_string();
+        assert!(!content.contains("CMAKE_C_FLAGS_INIT"));
+        assert_eq!(
+            content
+                .matches(&format!("include(\"{}\")\n", flags_file.display()))
+                .count(),
+            1
+        );
+        assert_eq!(
+            toolchain.to_cmake_flags_string(),
+            "string(JOIN \" \" CMAKE_C_FLAGS_INIT\n  \"-O2\"\n  \"-DNAME=\\\"some value\\\"\"\n)\n\
+             string(JOIN \" \" CMAKE_EXE_LINKER_FLAGS_INIT\n  \"-Wl,--gc-sections\"\n)\n"
+        );
+        let defines = toolchain.as_cmake_defines();
+        assert!(defines.contains(&(
+            "CMAKE_PROJECT_INCLUDE_BEFORE".to_string(),
+            flags_file.display().to_string()
+        )));
+        assert!(!defines.iter().any(|(var, _)| var.ends_with("FLAGS_INIT")));
+
+        let toolchain_file = dir.join("toolchain.cmake");
+        toolchain.write_to_file(&toolchain_file).unwrap();
+        assert_eq!(
+            fs::read_to_string(&flags_file).unwrap(),
+            toolchain.to_cmake_flags_string()
+        );
+        fs::remove_dir_all(&dir).unwrap();
+
+        // `string(JOIN)` needs CMake 3.12
+        toolchain.min_cmake_version("3.5".to_string());
+        assert_eq!(
+            toolchain.to_cmake_flags_string(),
+            "set(CMAKE_C_FLAGS_INIT\n  \"-O2\"\n  \"-DNAME=\\\"some value\\\"\"\n)\n\
+             string(REPLACE \";\" \" \" CMAKE_C_FLAGS_INIT \"${CMAKE_C_FLAGS_INIT}\")\n\
+             set(CMAKE_EXE_LINKER_FLAGS_INIT\n  \"-Wl,--gc-sections\"\n)\n\
+             string(REPLACE \";\" \" \" CMAKE_EXE_LINKER_FLAGS_INIT \"${CMAKE_EXE_LINKER_FLAGS_INIT}\")\n"
+        );
+    }
+
+    #[test]
+    fn test_header() {
+        let mut toolchain = with_env(&[], || {
+            CMakeToolchain::new_without_probe("aarch64-unknown-linux-gnu")
+        });
+        toolchain.header_comment("Project: foo\nComponent: bar".to_string());
+        let content = toolchain.to_cmake_string();
+        assert!(content.starts_with(&format!(
+            "# CMake toolchain file generated for target aarch64-unknown-linux-gnu by cmake-toolchain {}\n\
+             # Project: foo\n\
+             # Component: bar\n",
+            env!("CARGO_PKG_VERSION")
+        )));
+        assert!(!content.contains("# Generated at"));
+
+        toolchain.timestamp(true);
+        assert!(toolchain.to_cmake_string().contains("# Generated at "));
+    }
+
+    #[test]
+    fn test_emit_to_out_dir() {
+        let out_dir = temp_dir("cmake-toolchain-test-emit-to-out-dir");
+        fs::create_dir_all(&out_dir).unwrap();
+        let toolchain = with_env(&[], || {
+            CMakeToolchain::new_without_probe("aarch64-unknown-linux-gnu")
+        });
+        let consulted = toolchain.get_consulted_env_vars();
+        assert!(consulted.contains(&"CC_aarch64-unknown-linux-gnu".to_string()));
+        assert!(consulted.contains(&"CROSS_COMPILE".to_string()));
+
+        let emit = |vars: &[(&str, &str)]| {
+            let mut vars = vars.to_vec();
+            vars.push(("OUT_DIR", out_dir.to_str().unwrap()));
+            with_env(&vars, || toolchain.emit_to_out_dir().unwrap())
+        };
+        let path = emit(&[]);
+        assert_eq!(
+            path,
+            out_dir.join("cmake-toolchain-aarch64-unknown-linux-gnu.cmake")
+        );
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            toolchain.to_cmake_string()
+        );
+        let hash_path = out_dir.join("cmake-toolchain-aarch64-unknown-linux-gnu.cmake.hash");
+        assert!(hash_path.is_file());
+
+        // Unchanged inputs leave the file alone
+        fs::write(&path, "edited").unwrap();
+        emit(&[]);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "edited");
+
+        // A consulted environment variable changed
+        emit(&[("CROSS_COMPILE", "aarch64-none-linux-gnu-")]);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            toolchain.to_cmake_string()
+        );
+        fs::remove_dir_all(&out_dir).unwrap();
+    }
+
+    #[test]
+    fn test_env_var_names() {
+        let toolchain = with_env(&[], || {
+            CMakeToolchain::new_without_probe("thumbv8m.main-none-eabi")
+        });
+        let names = toolchain.env_var_names();
+        for var in [
+            "CC",
+            "TARGET_CC",
+            "CC_thumbv8m.main-none-eabi",
+            "CC_thumbv8m_main_none_eabi",
+            "CFLAGS_thumbv8m.main_none_eabi",
+            "TARGET_CXXFLAGS",
+            "AR",
+            "RANLIB_thumbv8m.main-none-eabi",
+            "CROSS_COMPILE",
+        ] {
+            assert!(names.contains(var), "{}", var);
+        }
+        assert!(!names.contains("HOST_CC"));
+    }
+
+    #[test]
+    fn test_to_cmake_string_is_reproducible() {
+        let meta = rustc_version::version_meta().unwrap();
+        let mut first = with_env(&[], || CMakeToolchain::new(&meta.host));
+        first
+            .define("ZLIB_ROOT", "/opt/zlib")
+            .define("OPENSSL_ROOT_DIR", "/opt/openssl");
+        let mut second = with_env(&[], || CMakeToolchain::new(&meta.host));
+        second
+            .define("OPENSSL_ROOT_DIR", "/opt/openssl")
+            .define("ZLIB_ROOT", "/opt/zlib");
+        let content = first.to_cmake_string();
+        assert_eq!(content.as_bytes(), second.to_cmake_string().as_bytes());
+        assert_eq!(content.as_bytes(), first.to_cmake_string().as_bytes());
+        assert!(!content.contains('\r'));
+        assert!(content.find("OPENSSL_ROOT_DIR").unwrap() < content.find("ZLIB_ROOT").unwrap());
+    }
+
+    #[test]
+    fn test_native_toolchain() {
+        let meta = rustc_version::version_meta().unwrap();
+        let mut toolchain = with_env(&[], || CMakeToolchain::new(&meta.host));
+        toolchain.add_find_root_path("/opt/extra".into());
+        assert!(!toolchain.is_cross_compiling());
+        let content = toolchain.to_cmake_string();
+        assert!(!content.contains("CMAKE_SYSTEM_NAME"));
+        assert!(!content.contains("CMAKE_FIND_ROOT_PATH"));
+        assert!(content.contains("CMAKE_C_COMPILER"));
+    }
+
+    #[test]
+    fn test_to_cmake_string() {
+        let mut toolchain = with_env(&[], || CMakeToolchain::new("armv7-unknown-linux-gnueabihf"));
+        assert!(toolchain.is_cross_compiling());
+        toolchain
+            .cc("/usr/bin/gcc".into())
+            .cxx("/usr/bin/g++".into());
+        let content = toolchain.to_cmake_string();
+        assert!(content.contains("set(CMAKE_C_COMPILER \"/usr/bin/gcc\")\n"));
+        assert!(content.contains("set(CMAKE_CXX_COMPILER \"/usr/bin/g++\")\n"));
+        assert!(!content.contains("CMAKE_SYSROOT"));
+        let system_name = content.find("CMAKE_SYSTEM_NAME").unwrap();
+        assert!(system_name < content.find("CMAKE_C_COMPILER").unwrap());
+
+        assert!(!content.contains("CMAKE_FIND_ROOT_PATH"));
+
+        toolchain.cc("C:/Program Files/LLVM/bin/clang \"v1\".exe".into());
+        let content = toolchain.to_cmake_string();
+        assert!(content.contains(
+            "set(CMAKE_C_COMPILER \"C:/Program Files/LLVM/bin/clang \\\"v1\\\".exe\")\n"
+        ));
+        toolchain.cc("/usr/bin/gcc".into());
+        toolchain.compiler_target("arm-linux gnueabihf".to_string());
+        let content = toolchain.to_cmake_string();
+        assert!(content.contains("set(CMAKE_C_COMPILER_TARGET \"arm-linux gnueabihf\")\n"));
+        toolchain.compiler_target("armv7-unknown-linux-gnueabihf".to_string());
+
+        toolchain
+            .add_c_flag("-march=armv7-a")
+            .add_c_flag("-mfpu=neon");
+        let content = toolchain.to_cmake_string();
+        assert!(content.contains("set(CMAKE_C_FLAGS_INIT \"-march=armv7-a -mfpu=neon\")\n"));
+
+        toolchain.sysroot("/opt/sysroot".into());
+        toolchain.add_find_root_path("/opt/extra".into());
+        let content = toolchain.to_cmake_string();
+        assert!(content.contains("set(CMAKE_SYSROOT \"/opt/sysroot\")\n"));
+        assert!(content.contains("set(CMAKE_FIND_ROOT_PATH \"/opt/sysroot\" \"/opt/extra\")\n"));
+        assert!(content.contains("set(CMAKE_FIND_ROOT_PATH_MODE_PROGRAM NEVER)\n"));
+        assert!(content.contains("set(CMAKE_FIND_ROOT_PATH_MODE_LIBRARY ONLY)\n"));
+
+        assert!(!content.contains("CMAKE_LINKER"));
+        toolchain.linker("/usr/bin/ld.lld".into());
+        let content = toolchain.to_cmake_string();
+        assert!(content.contains("set(CMAKE_LINKER \"/usr/bin/ld.lld\")\n"));
+
+        assert!(!content.contains("CMAKE_STAGING_PREFIX"));
+        assert!(!content.contains("CMAKE_POSITION_INDEPENDENT_CODE"));
+        toolchain
+            .position_independent_code(true)
+            .interprocedural_optimization(false);
+        let content = toolchain.to_cmake_string();
+        assert!(content.contains("set(CMAKE_POSITION_INDEPENDENT_CODE ON)\n"));
+        assert!(content.contains("set(CMAKE_INTERPROCEDURAL_OPTIMIZATION OFF)\n"));
+        assert!(!content.contains("CMAKE_VERBOSE_MAKEFILE"));
+
+        assert!(!content.contains("CMAKE_CXX_STANDARD"));
+        toolchain.cxx_standard(17).cxx_standard_required(true);
+        let content = toolchain.to_cmake_string();
+        assert!(
+            content.contains("set(CMAKE_CXX_STANDARD 17)\nset(CMAKE_CXX_STANDARD_REQUIRED ON)\n")
+        );
+        assert!(!content.contains("CMAKE_C_STANDARD"));
+        let mut invalid = toolchain.clone();
+        invalid.c_standard(14);
+        assert!(matches!(
+            invalid.validate(),
+            Err(Error::InvalidStandard { standard: 14, .. })
+        ));
+        toolchain
+            .staging_prefix("/opt/staging".into())
+            .install_prefix("/usr/local".into());
+        let content = toolchain.to_cmake_string();
+        assert!(content.contains("set(CMAKE_STAGING_PREFIX \"/opt/staging\")\n"));
+        assert!(content.contains("set(CMAKE_INSTALL_PREFIX \"/usr/local\")\n"));
+
+        toolchain.sysroot_as_flag(true);
+        let content = toolchain.to_cmake_string();
+        assert!(content.contains(
+            "set(CMAKE_C_FLAGS_INIT \"-march=armv7-a -mfpu=neon --sysroot=/opt/sysroot\")\n"
+        ));
+        assert!(content.contains("set(CMAKE_EXE_LINKER_FLAGS_INIT \"--sysroot=/opt/sysroot\")\n"));
+        toolchain.sysroot_as_flag(false);
+
+        toolchain
+            .min_cmake_version("2.8".to_string())
+            .compiler_target("armv7-unknown-linux-gnueabihf".to_string());
+        let content = toolchain.to_cmake_string();
+        assert!(content.contains("# Assumes CMake 2.8 or newer\n"));
+        assert!(!content.contains("CMAKE_SYSROOT"));
+        assert!(!content.contains("CMAKE_C_COMPILER_TARGET"));
+        assert!(content.contains(
+            "set(CMAKE_EXE_LINKER_FLAGS_INIT \"--target=armv7-unknown-linux-gnueabihf --sysroot=/opt/sysroot\")\n"
+        ));
+        toolchain.min_cmake_version("3.20".to_string());
+        let content = toolchain.to_cmake_string();
+        assert!(content.contains("set(CMAKE_SYSROOT \"/opt/sysroot\")\n"));
+        assert!(!content.contains("--sysroot"));
+
+        toolchain
+            .include_before("/opt/vendor/toolchain.cmake".into())
+            .include_before("/opt/vendor/extra.cmake".into())
+            .include_after("/opt/vendor/overrides.cmake".into());
+        let content = toolchain.to_cmake_string();
+        let vendor = content
+            .find("include(\"/opt/vendor/toolchain.cmake\")\n")
+            .unwrap();
+        let extra = content
+            .find("include(\"/opt/vendor/extra.cmake\")\n")
+            .unwrap();
+        let overrides = content
+            .find("include(\"/opt/vendor/overrides.cmake\")\n")
+            .unwrap();
+        assert!(vendor < extra);
+        assert!(extra < content.find("CMAKE_SYSTEM_NAME").unwrap());
+        assert!(overrides > content.find("CMAKE_AR").unwrap());
+
+        toolchain
+            .define("OPENSSL_ROOT_DIR", "/opt/openssl")
+            .define("CMAKE_SYSROOT", "/opt/other sysroot");
+        let content = toolchain.to_cmake_string();
+        assert!(content.ends_with(
+            "set(CMAKE_SYSROOT \"/opt/other sysroot\")\nset(OPENSSL_ROOT_DIR /opt/openssl)\n"
+        ));
+        assert_eq!(content.matches("CMAKE_SYSROOT").count(), 1);
+
+        toolchain.define("GREETING", "say \"hi\" for $5");
+        let content = toolchain.to_cmake_string();
+        assert!(content.contains("set(GREETING \"say \\\"hi\\\" for \\$5\")\n"));
     }
 }
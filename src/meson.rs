@@ -0,0 +1,143 @@
+use std::path::Path;
+
+use crate::{is_zig, zig_target, CMakeToolchain, Endianness};
+
+impl CMakeToolchain {
+    /// Render a Meson cross file using the same tools as the CMake toolchain file
+    pub fn to_meson_cross_file(&self) -> String {
+        let mut content = String::new();
+        content.push_str("[binaries]\n");
+        let launcher = self.compiler_launcher.as_deref();
+        for (name, compiler, subcommand, args) in [
+            ("c", &self.cc, "cc", &self.cc_args),
+            ("cpp", &self.cxx, "c++", &self.cxx_args),
+        ] {
+            let mut command: Vec<String> = launcher
+                .into_iter()
+                .chain([compiler.as_path()])
+                .map(meson_path)
+                .collect();
+            if is_zig(compiler) {
+                let target = zig_target(&self.target);
+                command.extend([subcommand, "-target", &target].map(meson_string));
+            }
+            command.extend(args.iter().map(|arg| meson_string(arg)));
+            content.push_str(&format!("{} = [{}]\n", name, command.join(", ")));
+        }
+        for (name, tool) in [("ar", Some(&self.ar)), ("ranlib", self.ranlib.as_ref())] {
+            match tool {
+                Some(tool) if is_zig(tool) => content.push_str(&format!(
+                    "{} = [{}, {}]\n",
+                    name,
+                    meson_path(tool),
+                    meson_string(name)
+                )),
+                Some(tool) => content.push_str(&format!("{} = {}\n", name, meson_path(tool))),
+                None => {}
+            }
+        }
+        for (name, tool) in [
+            ("fortran", &self.fortran),
+            ("strip", &self.strip),
+            ("nm", &self.nm),
+            ("objcopy", &self.objcopy),
+            ("objdump", &self.objdump),
+            ("windres", &self.rc),
+        ] {
+            if let Some(tool) = tool {
+                content.push_str(&format!("{} = {}\n", name, meson_path(tool)));
+            }
+        }
+        let emulator = self.get_emulator();
+        if !emulator.is_empty() {
+            let command: Vec<String> = emulator.iter().map(|arg| meson_string(arg)).collect();
+            content.push_str(&format!("exe_wrapper = [{}]\n", command.join(", ")));
+        }
+
+        let mut options = Vec::new();
+        let arch_flags = self.arch_flags();
+        let c_args = [&arch_flags[..], &self.cc_tool_flags(), &self.c_flags].concat();
+        let cpp_args = [&arch_flags[..], &self.cxx_tool_flags(), &self.cxx_flags].concat();
+        for (name, flags) in [
+            ("c_args", &c_args),
+            ("cpp_args", &cpp_args),
+            ("c_link_args", &self.exe_linker_flags),
+            ("cpp_link_args", &self.exe_linker_flags),
+        ] {
+            if !flags.is_empty() {
+                let flags: Vec<String> = flags.iter().map(|flag| meson_string(flag)).collect();
+                options.push(format!("{} = [{}]\n", name, flags.join(", ")));
+            }
+        }
+        if !options.is_empty() {
+            content.push_str("\n[built-in options]\n");
+            content.push_str(&options.concat());
+        }
+
+        if let Some(sysroot) = &self.sysroot {
+            content.push_str("\n[properties]\n");
+            content.push_str(&format!("sys_root = {}\n", meson_path(sysroot)));
+        }
+
+        let arch = self.target.split('-').next().unwrap_or_default();
+        content.push_str("\n[host_machine]\n");
+        content.push_str(&format!(
+            "system = {}\n",
+            meson_string(&self.meson_system())
+        ));
+        content.push_str(&format!(
+            "cpu_family = {}\n",
+            meson_string(meson_cpu_family(arch))
+        ));
+        content.push_str(&format!("cpu = {}\n", meson_string(arch)));
+        let endian = match self.endianness() {
+            Endianness::Little => "little",
+            Endianness::Big => "big",
+        };
+        content.push_str(&format!("endian = {}\n", meson_string(endian)));
+        content
+    }
+
+    /// Meson `host_machine.system()` of the target
+    fn meson_system(&self) -> String {
+        if self.target.contains("android") {
+            "android".to_string()
+        } else if self.system_name == "Generic" {
+            // Meson calls bare metal systems `none`
+            "none".to_string()
+        } else {
+            self.system_name.to_lowercase()
+        }
+    }
+}
+
+/// Map a Rust architecture to the Meson `host_machine.cpu_family()`
+fn meson_cpu_family(arch: &str) -> &str {
+    if (arch.starts_with("arm") && arch != "arm64") || arch.starts_with("thumb") {
+        "arm"
+    } else if arch.starts_with("riscv64") {
+        "riscv64"
+    } else if arch.starts_with("riscv32") {
+        "riscv32"
+    } else {
+        match arch {
+            "arm64" => "aarch64",
+            "i386" | "i586" | "i686" => "x86",
+            "powerpc" => "ppc",
+            "powerpc64" | "powerpc64le" => "ppc64",
+            "mips" | "mipsel" | "mipsisa32r6" | "mipsisa32r6el" => "mips",
+            "mips64" | "mips64el" | "mipsisa64r6" | "mipsisa64r6el" => "mips64",
+            "sparcv9" => "sparc64",
+            _ => arch,
+        }
+    }
+}
+
+/// Quote a Meson string, escaping backslashes and single quotes
+fn meson_string(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+fn meson_path(path: &Path) -> String {
+    meson_string(&path.to_string_lossy())
+}
@@ -0,0 +1,83 @@
+use std::fmt;
+use std::path::PathBuf;
+
+/// How a tool of the toolchain was resolved
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ToolSource {
+    /// Set explicitly through a setter
+    Explicit,
+    /// Read from the named environment variable
+    Env(String),
+    /// Read from the `[target.<triple>]` table of the named Cargo configuration file
+    CargoConfig(PathBuf),
+    /// Derived from a cross compile prefix like `aarch64-linux-gnu`
+    Prefix(String),
+    /// Found in the named SDK, like the Android NDK
+    Sdk(String),
+    /// Found in the tool search directory, next to the C compiler by default
+    SearchDir(PathBuf),
+    /// Detected by the cc crate
+    Detected,
+    /// Fallback to the default tool name
+    Default,
+}
+
+impl fmt::Display for ToolSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ToolSource::Explicit => write!(f, "set explicitly"),
+            ToolSource::Env(var) => write!(f, "environment variable {}", var),
+            ToolSource::CargoConfig(path) => write!(f, "Cargo configuration {}", path.display()),
+            ToolSource::Prefix(prefix) => write!(f, "prefix {}", prefix),
+            ToolSource::Sdk(sdk) => write!(f, "{}", sdk),
+            ToolSource::SearchDir(dir) => write!(f, "found in {}", dir.display()),
+            ToolSource::Detected => write!(f, "detected by the cc crate"),
+            ToolSource::Default => write!(f, "default"),
+        }
+    }
+}
+
+/// Resolution of a single tool
+#[derive(Debug, Clone)]
+pub struct ToolReport {
+    /// Tool name, like `cc` or `ar`
+    pub name: &'static str,
+    /// Resolved path, `None` if the tool isn't available
+    pub path: Option<PathBuf>,
+    /// How the tool was resolved
+    pub source: Option<ToolSource>,
+}
+
+/// Report of how each tool of a toolchain was resolved
+#[derive(Debug, Clone)]
+pub struct ToolchainReport {
+    /// Host target
+    pub host: String,
+    /// Build target
+    pub target: String,
+    /// Resolved tools
+    pub tools: Vec<ToolReport>,
+}
+
+impl fmt::Display for ToolchainReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "CMake toolchain for target {} on host {}",
+            self.target, self.host
+        )?;
+        for tool in &self.tools {
+            write!(f, "  {:<8} ", tool.name)?;
+            match &tool.path {
+                Some(path) => write!(f, "{}", path.display())?,
+                None => write!(f, "not found")?,
+            }
+            if let Some(source) = &tool.source {
+                write!(f, " ({})", source)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
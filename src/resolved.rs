@@ -0,0 +1,29 @@
+use std::ops::Deref;
+use std::sync::Arc;
+
+use crate::CMakeToolchain;
+
+/// A validated toolchain that can't be changed anymore, see [`CMakeToolchain::build`]
+///
+/// Only the getters and emission methods of [`CMakeToolchain`] are reachable through
+/// [`Deref`]. Cloning is cheap, so it can be shared by parallel CMake invocations.
+#[derive(Debug, Clone)]
+pub struct Resolved {
+    toolchain: Arc<CMakeToolchain>,
+}
+
+impl Resolved {
+    pub(crate) fn new(toolchain: CMakeToolchain) -> Self {
+        Self {
+            toolchain: Arc::new(toolchain),
+        }
+    }
+}
+
+impl Deref for Resolved {
+    type Target = CMakeToolchain;
+
+    fn deref(&self) -> &CMakeToolchain {
+        &self.toolchain
+    }
+}
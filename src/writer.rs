@@ -0,0 +1,185 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Writer for the contents of a CMake toolchain file
+pub(crate) struct CMakeWriter<'a> {
+    content: String,
+    /// Variables set so far with their unquoted values
+    variables: Vec<(String, String)>,
+    /// User defined variables, they replace the standard variables of the same name
+    defines: &'a BTreeMap<String, String>,
+    /// File the flag sets are moved to, with its contents
+    flags_file: Option<(&'a Path, String)>,
+    /// Whether `string(JOIN)` can be used in the flags file, it needs CMake 3.12
+    string_join: bool,
+}
+
+impl<'a> CMakeWriter<'a> {
+    pub(crate) fn new(defines: &'a BTreeMap<String, String>) -> Self {
+        Self {
+            content: String::new(),
+            variables: Vec::new(),
+            defines,
+            flags_file: None,
+            string_join: true,
+        }
+    }
+
+    /// Move the flag sets to `path`, which the toolchain file includes
+    ///
+    /// Without `string_join` the flags are set as a list that's joined with `string(REPLACE)`,
+    /// for CMake older than 3.12.
+    pub(crate) fn flags_file(&mut self, path: &'a Path, string_join: bool) {
+        self.flags_file = Some((path, String::new()));
+        self.string_join = string_join;
+    }
+
+    /// Append a `# <comment>` line
+    pub(crate) fn comment(&mut self, comment: &str) {
+        self.content.push_str(&format!("# {}\n", comment));
+    }
+
+    /// Append an `include("<path>")` line
+    pub(crate) fn include(&mut self, path: &Path) {
+        self.content
+            .push_str(&format!("include({})\n", quote(&cmake_path(path))));
+    }
+
+    /// Append a `set(<var> <value>)` line, quoting `value` only when it has special characters
+    pub(crate) fn set(&mut self, var: &str, value: &str) {
+        self.push(var, &argument(value), value);
+    }
+
+    /// Append a `set(<var> "<value>")` line, escaping `value` as needed
+    pub(crate) fn set_string(&mut self, var: &str, value: &str) {
+        self.push(var, &quote(value), value);
+    }
+
+    /// Append a `set(<var> "<path>")` line
+    pub(crate) fn set_path(&mut self, var: &str, path: &Path) {
+        self.set_string(var, &cmake_path(path));
+    }
+
+    /// Append a `set(<var> "<path>"...)` line for a list of paths
+    pub(crate) fn set_paths(&mut self, var: &str, paths: &[&Path]) {
+        let paths: Vec<String> = paths.iter().map(|path| cmake_path(path)).collect();
+        let quoted: Vec<String> = paths.iter().map(|path| quote(path)).collect();
+        self.push(var, &quoted.join(" "), &paths.join(";"));
+    }
+
+    /// Set `var` to the space separated `flags`
+    ///
+    /// With a flags file the value is joined there, one flag per line, and the toolchain file
+    /// includes the flags file in place of the first flag set.
+    pub(crate) fn set_flags(&mut self, var: &str, flags: &[String]) {
+        let (path, content) = match &mut self.flags_file {
+            Some((path, content)) if !self.defines.contains_key(var) => (*path, content),
+            _ => return self.set_string(var, &flags.join(" ")),
+        };
+        if content.is_empty() {
+            self.content
+                .push_str(&format!("include({})\n", quote(&cmake_path(path))));
+            self.variables
+                .push(("CMAKE_PROJECT_INCLUDE_BEFORE".to_string(), cmake_path(path)));
+        }
+        if self.string_join {
+            content.push_str(&format!("string(JOIN \" \" {}\n", var));
+        } else {
+            content.push_str(&format!("set({}\n", var));
+        }
+        for flag in flags {
+            content.push_str(&format!("  {}\n", quote(flag)));
+        }
+        content.push_str(")\n");
+        if !self.string_join {
+            content.push_str(&format!(
+                "string(REPLACE \";\" \" \" {} \"${{{}}}\")\n",
+                var, var
+            ));
+        }
+    }
+
+    /// Append a `set(<var> <argument>)` line, `value` is the unquoted value of `argument`
+    fn push(&mut self, var: &str, argument: &str, value: &str) {
+        if !self.defines.contains_key(var) {
+            self.content
+                .push_str(&format!("set({} {})\n", var, argument));
+            self.variables.push((var.to_string(), value.to_string()));
+        }
+    }
+
+    /// Append the user defined variables and return the file contents
+    pub(crate) fn finish(mut self) -> String {
+        for (var, value) in self.defines {
+            self.content
+                .push_str(&format!("set({} {})\n", var, argument(value)));
+        }
+        self.content
+    }
+
+    /// Return the contents of the flags file
+    pub(crate) fn finish_flags(self) -> String {
+        self.flags_file
+            .map(|(_, content)| content)
+            .unwrap_or_default()
+    }
+
+    /// Return the variables set, followed by the user defined variables
+    pub(crate) fn into_defines(self) -> Vec<(String, String)> {
+        let mut variables = self.variables;
+        for (var, value) in self.defines {
+            variables.push((var.clone(), value.clone()));
+        }
+        variables
+    }
+}
+
+/// Wrap `value` in a CMake quoted argument
+///
+/// Backslashes, double quotes and `$` are escaped so the value is taken literally.
+pub(crate) fn quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        if matches!(c, '\\' | '"' | '$') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Pass `value` as a bare CMake argument when that's unambiguous, quoting it otherwise
+fn argument(value: &str) -> String {
+    let special = |c: char| c.is_whitespace() || "\"\\$;()#".contains(c);
+    if value.is_empty() || value.contains(special) {
+        quote(value)
+    } else {
+        value.to_string()
+    }
+}
+
+/// Quote a single flag of a space separated flags variable like `CMAKE_C_FLAGS_INIT`
+///
+/// CMake pastes the flags into the compiler command line as is, so a flag with whitespace is
+/// wrapped in double quotes to stay a single argument.
+pub(crate) fn quote_flag(flag: &str) -> String {
+    if flag.contains(char::is_whitespace) || flag.contains('"') {
+        format!("\"{}\"", flag.replace('"', "\\\""))
+    } else {
+        flag.to_string()
+    }
+}
+
+/// Convert a path to the form CMake expects
+///
+/// CMake treats backslashes as escape characters, so Windows paths must use forward slashes.
+pub(crate) fn cmake_path(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    if cfg!(windows) {
+        path.replace('\\', "/")
+    } else {
+        path.into_owned()
+    }
+}